@@ -1,35 +1,83 @@
-use crate::cli::{CrudCommands, DatabaseCommands, OutputFormat, TableCommands};
+use crate::cli::completion::changes_catalog;
+use crate::cli::meta::MetaCommand;
+use crate::cli::output::{formatter, Records, RowWriter};
+use crate::cli::pager;
+use crate::cli::repl::{LineEditor, ReadLine, ReplOptions};
+use crate::cli::splitter::{command_tag, is_read_only, Pending, StatementSplitter};
+use crate::cli::variables::{bind, Variables};
+use crate::cli::{
+    CrudCommands, DatabaseCommands, DisplayOptions, ExpandedMode, OutputFormat, ProfileCommands,
+    TableCommands,
+};
+use crate::config::pgpass::lookup_password;
+use crate::config::{load_config, save_config};
 use crate::db::client::PostgresClient;
+use crate::db::params::QueryParams;
+use crate::error::{DconError, SqlError};
+use crate::interrupt::{self, Hold};
+use crate::models::connection::{ConnectionConfig, ConnectionInfo};
+use crate::models::profile::{Profile, ProfileInfo};
+use crate::models::table::TableDetails;
+use crate::models::timing::QueryTiming;
+use crate::status;
 use colored::*;
+use dialoguer::Password;
+use regex::Regex;
+use serde::Serialize;
 use serde_json::Value;
-use std::error::Error;
-use std::io::{self, Write};
-use tabled::Table;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tabled::{Table, Tabled};
+use tokio_postgres::{Column, Row};
+
+/// Connects to the server. Without a password in `config`, one is looked up
+/// in the password file for the database actually connected to. It is only
+/// prompted for (without echo) once the server has asked for one, and never
+/// when `no_password` is set or stdin is not a terminal.
+pub async fn connect(
+    config: &ConnectionConfig,
+    no_password: bool,
+) -> Result<PostgresClient, DconError> {
+    let mut config = config.clone();
+    if config.password.is_none() {
+        config.password = lookup_password(&config)?;
+    }
+
+    match PostgresClient::new(&config).await {
+        Err(DconError::PasswordRequired) if !no_password && io::stdin().is_terminal() => {
+            let password = Password::new()
+                .with_prompt(format!("Password for user {}", config.user))
+                .allow_empty_password(true)
+                .interact()?;
+
+            config.password = Some(password);
+            PostgresClient::new(&config).await
+        }
+        result => result,
+    }
+}
 
 pub async fn execute_connect(
     client: &PostgresClient,
     format: &OutputFormat,
-) -> Result<(), Box<dyn Error>> {
-    println!("{}", "📡 Connection Information:".bright_green().bold());
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
+    status!("{}", "📡 Connection Information:".bright_green().bold());
 
     let conn_info = client.get_connection_info().await?;
 
-    match format {
-        OutputFormat::Table => {
-            let table = Table::new(&conn_info);
-            println!("{table}");
-        }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&conn_info)?;
-            println!("{json}");
-        }
-        OutputFormat::Csv => {
-            println!("Property,Value");
-            for info in conn_info {
-                println!("{},{}", info.property, info.value);
-            }
-        }
-    }
+    print_records(
+        &Records::from_items("connection_info", &conn_info)?,
+        format,
+        display,
+    )?;
 
     Ok(())
 }
@@ -38,32 +86,19 @@ pub async fn execute_database_command(
     client: &PostgresClient,
     command: &DatabaseCommands,
     format: &OutputFormat,
-) -> Result<(), Box<dyn Error>> {
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
     match command {
         DatabaseCommands::List => {
-            println!("{}", "🗄️ Available Databases:".bright_green().bold());
+            status!("{}", "🗄️ Available Databases:".bright_green().bold());
 
             let databases = client.list_databases().await?;
 
-            match format {
-                OutputFormat::Table => {
-                    let table = Table::new(&databases);
-                    println!("{table}");
-                }
-                OutputFormat::Json => {
-                    let json = serde_json::to_string_pretty(&databases)?;
-                    println!("{json}");
-                }
-                OutputFormat::Csv => {
-                    println!("Database Name,Owner,Encoding,Size,Description");
-                    for db in databases {
-                        println!(
-                            "{},{},{},{},{}",
-                            db.name, db.owner, db.encoding, db.size, db.description
-                        );
-                    }
-                }
-            }
+            print_records(
+                &Records::from_items("databases", &databases)?,
+                format,
+                display,
+            )?;
         }
 
         DatabaseCommands::Create {
@@ -72,14 +107,13 @@ pub async fn execute_database_command(
             encoding,
         } => {
             if !confirm_action(&format!("Create database '{name}'?"))? {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
             client
                 .create_database(name, owner.as_deref(), encoding)
                 .await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Database '{name}' created successfully!").green()
             );
@@ -89,20 +123,26 @@ pub async fn execute_database_command(
             if !confirm
                 || !confirm_action(&format!("Drop database '{name}'? This cannot be undone!"))?
             {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
             client.drop_database(name).await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Database '{name}' dropped successfully!").green()
             );
         }
 
         DatabaseCommands::Info { name: _ } => {
-            // This would show detailed database information
-            execute_connect(client, format).await?;
+            let details = client.get_database_details().await?;
+
+            let sections = [
+                Records::from_items("database", &details.properties())?,
+                Records::from_items("largest_relations", &details.largest_relations)?
+                    .titled("Largest Relations"),
+                Records::from_items("extensions", &details.extensions)?.titled("Extensions"),
+            ];
+            print_document(&details, &sections, format, display)?;
         }
     }
 
@@ -113,78 +153,43 @@ pub async fn execute_table_command(
     client: &PostgresClient,
     command: &TableCommands,
     format: &OutputFormat,
-) -> Result<(), Box<dyn Error>> {
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
     match command {
         TableCommands::List {
             database: _,
             system,
         } => {
-            println!("{}", "📋 Tables:".bright_green().bold());
+            status!("{}", "📋 Tables:".bright_green().bold());
 
             let tables = client.list_tables(*system).await?;
 
-            match format {
-                OutputFormat::Table => {
-                    let table = Table::new(&tables);
-                    println!("{table}");
-                }
-                OutputFormat::Json => {
-                    let json = serde_json::to_string_pretty(&tables)?;
-                    println!("{json}");
-                }
-                OutputFormat::Csv => {
-                    println!("Schema,Table Name,Type,Row Count");
-                    for table in tables {
-                        println!(
-                            "{},{},{},{}",
-                            table.schema, table.table_name, table.table_type, table.row_count
-                        );
-                    }
-                }
-            }
+            print_records(&Records::from_items("tables", &tables)?, format, display)?;
         }
 
         TableCommands::Describe { table, database: _ } => {
-            println!(
+            status!(
                 "{}",
                 format!("🔍 Table Structure: {table}").bright_green().bold()
             );
 
-            let columns = client.describe_table(table, None).await?;
+            let details = client.describe_table(table).await?;
 
-            match format {
-                OutputFormat::Table => {
-                    let table = Table::new(&columns);
-                    println!("{table}");
-                }
-                OutputFormat::Json => {
-                    let json = serde_json::to_string_pretty(&columns)?;
-                    println!("{json}");
-                }
-                OutputFormat::Csv => {
-                    println!("Column Name,Data Type,Nullable,Default,Primary Key");
-                    for col in columns {
-                        println!(
-                            "{},{},{},{},{}",
-                            col.column_name,
-                            col.data_type,
-                            col.is_nullable,
-                            col.default_value,
-                            col.is_primary
-                        );
-                    }
-                }
-            }
+            print_document(
+                &details,
+                &table_details_sections(&details)?,
+                format,
+                display,
+            )?;
         }
 
         TableCommands::Create { sql, database: _ } => {
             if !confirm_action("Execute CREATE TABLE statement?")? {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
-            client.execute_query(sql).await?;
-            println!("{}", "✅ Table created successfully!".green());
+            client.execute_query(sql, &QueryParams::default()).await?;
+            status!("{}", "✅ Table created successfully!".green());
         }
 
         TableCommands::Drop {
@@ -195,12 +200,11 @@ pub async fn execute_table_command(
             if !confirm
                 || !confirm_action(&format!("Drop table '{table}'? This cannot be undone!"))?
             {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
             client.drop_table(table).await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Table '{table}' dropped successfully!").green()
             );
@@ -214,18 +218,19 @@ pub async fn execute_crud_command(
     client: &PostgresClient,
     command: &CrudCommands,
     format: &OutputFormat,
-) -> Result<(), Box<dyn Error>> {
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
     match command {
         CrudCommands::Create {
             table,
             data,
             database: _,
         } => {
-            let json_data: Value =
-                serde_json::from_str(data).map_err(|e| format!("Invalid JSON data: {e}"))?;
+            let json_data: Value = serde_json::from_str(data)
+                .map_err(|e| DconError::UserInput(format!("Invalid JSON data: {e}")))?;
 
             let rows_affected = client.insert_data(table, &json_data).await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Inserted {rows_affected} row(s) into table '{table}'").green()
             );
@@ -240,14 +245,16 @@ pub async fn execute_crud_command(
             order,
             database: _,
         } => {
-            println!(
+            status!(
                 "{}",
                 format!("📖 Reading from table '{table}'")
                     .bright_green()
                     .bold()
             );
 
-            let rows = client
+            let table_name = table.to_string();
+            let mut printer = ResultPrinter::new(&table_name, format, display);
+            let result = client
                 .select_data(
                     table,
                     columns.as_deref(),
@@ -255,27 +262,16 @@ pub async fn execute_crud_command(
                     order.as_deref(),
                     *limit,
                     *offset,
+                    display.fetch_count(format),
+                    |columns, rows| printer.print(columns, rows),
                 )
                 .await?;
 
-            if rows.is_empty() {
-                println!("{}", "No data found.".yellow());
+            if printer.rows == 0 {
+                status!("{}", "No data found.".yellow());
                 return Ok(());
             }
-
-            // Display results based on format
-            match format {
-                OutputFormat::Table => {
-                    display_rows_as_table(&rows);
-                }
-                OutputFormat::Json => {
-                    let json_rows = rows_to_json(&rows)?;
-                    println!("{}", serde_json::to_string_pretty(&json_rows)?);
-                }
-                OutputFormat::Csv => {
-                    display_rows_as_csv(&rows);
-                }
-            }
+            printer.finish(result.columns())?;
         }
 
         CrudCommands::Update {
@@ -285,16 +281,15 @@ pub async fn execute_crud_command(
             database: _,
             confirm,
         } => {
-            let json_data: Value =
-                serde_json::from_str(data).map_err(|e| format!("Invalid JSON data: {e}"))?;
+            let json_data: Value = serde_json::from_str(data)
+                .map_err(|e| DconError::UserInput(format!("Invalid JSON data: {e}")))?;
 
             if !confirm || !confirm_action(&format!("Update table '{table}' WHERE {filter}?"))? {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
             let rows_affected = client.update_data(table, &json_data, filter).await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Updated {rows_affected} row(s) in table '{table}'").green()
             );
@@ -308,12 +303,11 @@ pub async fn execute_crud_command(
         } => {
             if !confirm || !confirm_action(&format!("Delete from table '{table}' WHERE {filter}?"))?
             {
-                println!("{}", "Operation cancelled.".yellow());
-                return Ok(());
+                return Err(DconError::Cancelled);
             }
 
             let rows_affected = client.delete_data(table, filter).await?;
-            println!(
+            status!(
                 "{}",
                 format!("✅ Deleted {rows_affected} row(s) from table '{table}'").green()
             );
@@ -323,40 +317,478 @@ pub async fn execute_crud_command(
     Ok(())
 }
 
+/// SQL read for `dcon query`, with the file it came from.
+pub struct Script {
+    /// File name, `-` for stdin, or `None` for `--sql`
+    pub source: Option<String>,
+    pub text: String,
+}
+
+/// Reads the SQL given with `--sql`, or else the `--file`s in order.
+pub fn read_scripts(sql: Option<&str>, files: &[PathBuf]) -> Result<Vec<Script>, DconError> {
+    if let Some(sql) = sql {
+        return Ok(vec![Script {
+            source: None,
+            text: sql.to_string(),
+        }]);
+    }
+
+    files
+        .iter()
+        .map(|path| {
+            let text = if path.as_os_str() == "-" {
+                io::read_to_string(io::stdin())
+                    .map_err(|e| DconError::io(e, "Failed to read SQL from stdin"))?
+            } else {
+                fs::read_to_string(path)
+                    .map_err(|e| DconError::io(e, format!("Failed to read '{}'", path.display())))?
+            };
+            Ok(Script {
+                source: Some(path.display().to_string()),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// How `dcon query` runs a script.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOptions {
+    /// Wrap everything in `BEGIN`/`COMMIT`, rolling back on the first error
+    pub single_transaction: bool,
+    /// Report failing statements and go on, like psql without `ON_ERROR_STOP`;
+    /// ignored with `single_transaction`, where every error is fatal
+    pub continue_on_error: bool,
+    /// `--param` values, bound to the `$n` of every statement that uses them
+    pub params: QueryParams,
+    /// `--var` values, for `:name` references
+    pub variables: Variables,
+}
+
 pub async fn execute_query(
     client: &PostgresClient,
-    sql: &str,
+    scripts: &[Script],
+    options: &ScriptOptions,
     format: &OutputFormat,
-) -> Result<(), Box<dyn Error>> {
-    println!("{}", "🔧 Executing custom query...".bright_green().bold());
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
+    status!("{}", "🔧 Executing custom query...".bright_green().bold());
 
-    let rows = client.execute_query(sql).await?;
+    let statements: Vec<_> = scripts
+        .iter()
+        .flat_map(|script| {
+            StatementSplitter::split(&script.text)
+                .into_iter()
+                .map(move |statement| (script.source.as_deref(), statement))
+        })
+        .collect();
+    if statements.is_empty() {
+        return Err(DconError::UserInput(
+            "No SQL statement to execute".to_string(),
+        ));
+    }
 
-    if rows.is_empty() {
-        println!(
-            "{}",
-            "Query executed successfully. No rows returned.".green()
-        );
-        return Ok(());
+    if options.single_transaction {
+        client.batch_execute("BEGIN").await?;
     }
 
-    match format {
-        OutputFormat::Table => {
-            display_rows_as_table(&rows);
+    let mut failed = 0;
+    for (source, statement) in &statements {
+        // Ctrl-C stops the whole script, even if the statement had ended before
+        // the cancel request reached the server.
+        if interrupt::take_pressed() {
+            return Err(DconError::Cancelled);
+        }
+        let mut printer = ResultPrinter::new("result", format, display);
+        let started = Instant::now();
+        let result = match bind(&statement.text, &options.params, &options.variables) {
+            Ok((sql, params)) => {
+                let result = client
+                    .stream_query(
+                        &sql,
+                        &params,
+                        display.fetch_count(format),
+                        |columns, rows| printer.print(columns, rows),
+                    )
+                    .await;
+                match result {
+                    Ok(result) if display.timing && display.stats => {
+                        server_stats(client, &sql, &params)
+                            .await
+                            .map(|stats| (result, Some(stats)))
+                    }
+                    result => result.map(|result| (result, None)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        let error = match result {
+            Ok((result, stats)) => {
+                if result.columns().is_empty() {
+                    status!(
+                        "{}",
+                        command_tag(&statement.text, result.rows_affected).green()
+                    );
+                } else {
+                    printer.finish(result.columns())?;
+                }
+                if display.timing {
+                    eprintln!("{}", timing_report(&result.timing, stats.as_deref()));
+                }
+                continue;
+            }
+            Err(e) => match source {
+                Some(source) => e.with_location(source, statement.line),
+                None => e,
+            },
+        };
+        if display.timing {
+            eprintln!("Time: {:.3} ms", millis(started.elapsed()));
+        }
+
+        if options.single_transaction {
+            // The error that matters is the statement's, not a failed rollback.
+            let _ = client.batch_execute("ROLLBACK").await;
+            status!("{}", "Transaction rolled back".yellow());
+            // Going on would run the rest outside the transaction.
+            return Err(error);
         }
-        OutputFormat::Json => {
-            let json_rows = rows_to_json(&rows)?;
-            println!("{}", serde_json::to_string_pretty(&json_rows)?);
+        // Ctrl-C stops the whole script.
+        if !options.continue_on_error || matches!(error, DconError::Cancelled) {
+            return Err(error);
         }
-        OutputFormat::Csv => {
-            display_rows_as_csv(&rows);
+
+        match format {
+            OutputFormat::Json => eprintln!("{}", error.to_json()),
+            _ => eprintln!("{}", error.report().red()),
         }
+        failed += 1;
+    }
+
+    if options.single_transaction {
+        client.batch_execute("COMMIT").await?;
+    }
+    if failed > 0 {
+        return Err(DconError::Sql(Box::new(SqlError::client(format!(
+            "{failed} of {} statements failed",
+            statements.len()
+        )))));
     }
 
     Ok(())
 }
 
-pub async fn execute_interactive_mode(client: &PostgresClient) -> Result<(), Box<dyn Error>> {
+/// `--stats`: the `Server:` line for a statement that just ran, from running
+/// it again under `EXPLAIN ANALYZE`. Statements that could change something
+/// are not run again, and failing to get the figures only warns.
+async fn server_stats(
+    client: &PostgresClient,
+    sql: &str,
+    params: &QueryParams,
+) -> Result<String, DconError> {
+    const NOT_AVAILABLE: &str = "Server: not available for this statement";
+    if !is_read_only(sql) {
+        return Ok(NOT_AVAILABLE.to_string());
+    }
+
+    match client.explain_analyze(sql, params).await {
+        Ok(stats) => Ok(format!(
+            "Server: planning {:.3} ms, execution {:.3} ms, buffers {}",
+            stats.planning_ms,
+            stats.execution_ms,
+            stats.buffers()
+        )),
+        Err(DconError::Cancelled) => Err(DconError::Cancelled),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                format!("Could not get server statistics: {e}").yellow()
+            );
+            Ok(NOT_AVAILABLE.to_string())
+        }
+    }
+}
+
+/// The `--timing` lines for a statement, such as
+/// `Time: 12.345 ms (first row 3.210 ms), 5 rows, 120 bytes`, followed by
+/// the `--stats` line if there is one.
+fn timing_report(timing: &QueryTiming, server: Option<&str>) -> String {
+    let mut report = format!("Time: {:.3} ms", millis(timing.elapsed));
+    if let Some(first_row) = timing.first_row {
+        report.push_str(&format!(" (first row {:.3} ms)", millis(first_row)));
+    }
+    report.push_str(&format!(
+        ", {} {}, {} bytes",
+        timing.rows,
+        if timing.rows == 1 { "row" } else { "rows" },
+        timing.bytes
+    ));
+    if let Some(server) = server {
+        report.push('\n');
+        report.push_str(server);
+    }
+
+    report
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// State of an interactive session.
+struct Session {
+    client: PostgresClient,
+    /// Never prompt for a password when reconnecting with `\c`
+    no_password: bool,
+    /// `\x`, `\pset` and `\timing`: expanded output, paging and timing
+    display: DisplayOptions,
+    /// `\set`: variables for `:name` references
+    variables: Variables,
+    /// `\o`: file receiving results instead of stdout
+    output: Option<File>,
+    /// Lines from `\i` and `\e`, run before more input is read
+    queue: VecDeque<String>,
+    /// The last statement executed, edited by `\e` when the buffer is empty
+    last_query: String,
+    /// Names for tab completion need loading again
+    catalog_stale: bool,
+}
+
+impl Session {
+    async fn run_sql(&mut self, sql: &str) {
+        let started = Instant::now();
+        let mut printer = ResultPrinter::new("result", &OutputFormat::Table, &self.display)
+            .with_output(self.output.as_mut());
+        let result = match bind(sql, &QueryParams::default(), &self.variables) {
+            Ok((bound, params)) => {
+                let result = self
+                    .client
+                    .stream_query(
+                        &bound,
+                        &params,
+                        self.display.fetch_count(&OutputFormat::Table),
+                        |columns, rows| printer.print(columns, rows),
+                    )
+                    .await;
+                match result {
+                    Ok(result) if self.display.timing && self.display.stats => {
+                        server_stats(&self.client, &bound, &params)
+                            .await
+                            .map(|stats| (result, Some(stats)))
+                    }
+                    result => result.map(|result| (result, None)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        let result = result.and_then(|(result, stats)| {
+            if !result.columns().is_empty() {
+                printer.finish(result.columns())?;
+            }
+            Ok((result, stats))
+        });
+
+        let report = match result {
+            Ok((result, stats)) => {
+                self.catalog_stale |= changes_catalog(sql);
+                if result.columns().is_empty() {
+                    println!("{}", command_tag(sql, result.rows_affected).green());
+                }
+                timing_report(&result.timing, stats.as_deref())
+            }
+            Err(e) => {
+                eprintln!("{}", e.report().red());
+                format!("Time: {:.3} ms", millis(started.elapsed()))
+            }
+        };
+
+        if self.display.timing {
+            println!("{report}");
+        }
+        // With its terminator, so that editing it with \e runs it again.
+        self.last_query = format!("{sql};");
+    }
+
+    async fn run_meta(&mut self, command: MetaCommand) -> Result<(), DconError> {
+        match command {
+            MetaCommand::Help => print_interactive_help(),
+            MetaCommand::ListDatabases => {
+                let databases = self.client.list_databases().await?;
+                self.emit_table(&databases);
+            }
+            MetaCommand::ListTables => {
+                let tables = self.client.list_tables(false).await?;
+                self.emit_table(&tables);
+            }
+            MetaCommand::Describe(name) => {
+                let details = self.client.describe_table(&name).await?;
+                self.emit(&table_details(&details)?);
+            }
+            MetaCommand::ListRelations { kinds, pattern } => {
+                let relations = self.client.list_relations(kinds, pattern.as_ref()).await?;
+                self.emit_table(&relations);
+            }
+            MetaCommand::ListIndexes(pattern) => {
+                let indexes = self.client.list_indexes(pattern.as_ref()).await?;
+                self.emit_table(&indexes);
+            }
+            MetaCommand::ListFunctions(pattern) => {
+                let functions = self.client.list_functions(pattern.as_ref()).await?;
+                self.emit_table(&functions);
+            }
+            MetaCommand::ListSchemas(pattern) => {
+                let schemas = self.client.list_schemas(pattern.as_ref()).await?;
+                self.emit_table(&schemas);
+            }
+            MetaCommand::ListRoles(pattern) => {
+                let roles = self.client.list_roles(pattern.as_ref()).await?;
+                self.emit_table(&roles);
+            }
+            MetaCommand::ListExtensions(pattern) => {
+                let extensions = self.client.list_extensions(pattern.as_ref()).await?;
+                self.emit_table(&extensions);
+            }
+            MetaCommand::Connect { database, user } => {
+                let mut config = self.client.config().clone();
+                if let Some(database) = database {
+                    config.database = database;
+                }
+                if let Some(user) = user.filter(|user| *user != config.user) {
+                    // The password belongs to the previous user.
+                    config.user = user;
+                    config.password = None;
+                }
+
+                match connect(&config, self.no_password).await {
+                    Ok(client) => {
+                        self.client = client;
+                        self.catalog_stale = true;
+                        println!(
+                            "You are now connected to database \"{}\" as user \"{}\".",
+                            config.database, config.user
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e.report().red());
+                        eprintln!("{}", "Previous connection kept".yellow());
+                    }
+                }
+            }
+            MetaCommand::ConnInfo => {
+                let config = self.client.config();
+                println!(
+                    "You are connected to database \"{}\" as user \"{}\" on host \"{}\" at port \"{}\".",
+                    config.database, config.user, config.host, config.port
+                );
+                if config.sslmode.uses_tls() {
+                    println!("SSL connection (sslmode={})", config.sslmode);
+                }
+            }
+            MetaCommand::Expanded(mode) => {
+                // Without an argument, toggle between on and off as psql does.
+                self.display.expanded = mode.unwrap_or(match self.display.expanded {
+                    ExpandedMode::Off => ExpandedMode::On,
+                    ExpandedMode::On | ExpandedMode::Auto => ExpandedMode::Off,
+                });
+                match self.display.expanded {
+                    ExpandedMode::Auto => println!("Expanded display is used automatically."),
+                    mode => println!("Expanded display is {mode}."),
+                }
+            }
+            MetaCommand::Pager(on) => {
+                self.display.pager = on.unwrap_or(!self.display.pager);
+                if self.display.pager {
+                    println!("Pager is used for long output.");
+                } else {
+                    println!("Pager usage is off.");
+                }
+            }
+            MetaCommand::Timing(on) => {
+                self.display.timing = on.unwrap_or(!self.display.timing);
+                println!("Timing is {}.", on_off(self.display.timing));
+            }
+            MetaCommand::Set(None) => {
+                for (name, value) in &self.variables {
+                    println!("{name} = '{value}'");
+                }
+            }
+            MetaCommand::Set(Some((name, value))) => {
+                self.variables.insert(name, value);
+            }
+            MetaCommand::Unset(name) => {
+                self.variables.remove(&name);
+            }
+            MetaCommand::Include(path) => {
+                let script = fs::read_to_string(&path).map_err(|e| {
+                    DconError::io(e, format!("Failed to read '{}'", path.display()))
+                })?;
+                // In front of the queue, so a nested \i runs before the rest of its parent.
+                for line in script.lines().rev() {
+                    self.queue.push_front(line.to_string());
+                }
+            }
+            MetaCommand::Output(None) => self.output = None,
+            MetaCommand::Output(Some(path)) => {
+                let file = File::create(&path).map_err(|e| {
+                    DconError::io(e, format!("Failed to open '{}'", path.display()))
+                })?;
+                self.output = Some(file);
+            }
+            // Handled by the input loop, which owns the query buffer.
+            MetaCommand::Quit | MetaCommand::ResetBuffer | MetaCommand::Edit => {}
+        }
+
+        Ok(())
+    }
+
+    fn emit_table<T: Tabled>(&mut self, items: &[T]) {
+        self.emit(&format!("{}\n", Table::new(items)));
+    }
+
+    /// Writes results to the `\o` file, or to stdout through the pager.
+    fn emit(&mut self, text: &str) {
+        match &mut self.output {
+            Some(file) => {
+                if let Err(e) = file.write_all(strip_colors(text).as_bytes()) {
+                    eprintln!(
+                        "{}",
+                        DconError::io(e, "Failed to write output").report().red()
+                    );
+                }
+            }
+            None => {
+                if let Err(e) = pager::page(text, self.display.pager) {
+                    eprintln!("{}", e.report().red());
+                }
+            }
+        }
+    }
+}
+
+pub async fn execute_interactive_mode(
+    client: PostgresClient,
+    no_password: bool,
+    display: DisplayOptions,
+    variables: Variables,
+    options: &ReplOptions,
+) -> Result<(), DconError> {
+    // Ctrl-C at the prompt only discards the line being entered.
+    let _hold = Hold::start();
+    let mut editor = LineEditor::new(options)?;
+    let mut splitter = StatementSplitter::new();
+    // Lines of the statement being entered, recorded in the history as one entry
+    let mut entry = String::new();
+    let mut session = Session {
+        client,
+        no_password,
+        display,
+        variables,
+        output: None,
+        queue: VecDeque::new(),
+        last_query: String::new(),
+        catalog_stale: true,
+    };
+
     println!(
         "{}",
         "🎯 Entering interactive mode. Type 'help' for commands, 'exit' to quit."
@@ -365,61 +797,336 @@ pub async fn execute_interactive_mode(client: &PostgresClient) -> Result<(), Box
     );
 
     loop {
-        print!("postgres> ");
-        io::stdout().flush()?;
+        if session.catalog_stale {
+            // Completion is a convenience; if the names cannot be loaded now
+            // (for example in an aborted transaction), try again next time.
+            if let Ok(catalog) = session.client.catalog_names().await {
+                editor.set_catalog(catalog);
+                session.catalog_stale = false;
+            }
+        }
+        editor.set_buffer(&entry);
+
+        // Lines queued by \i and \e are not typed, so they stay out of the history.
+        let (line, typed) = match session.queue.pop_front() {
+            Some(line) => (line, false),
+            None => {
+                let prompt = continuation_prompt(splitter.pending());
+                match tokio::task::block_in_place(|| editor.read_line(prompt))? {
+                    ReadLine::Line(line) => (line, true),
+                    ReadLine::Interrupted => {
+                        splitter.reset();
+                        entry.clear();
+                        continue;
+                    }
+                    ReadLine::Eof => {
+                        println!("{}", "👋 Goodbye!".bright_green());
+                        break;
+                    }
+                }
+            }
+        };
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim();
+        let command = line.trim();
 
-        if input.is_empty() {
-            continue;
+        if splitter.is_empty() {
+            match command.to_lowercase().as_str() {
+                "" => continue,
+                "exit" | "quit" => {
+                    println!("{}", "👋 Goodbye!".bright_green());
+                    break;
+                }
+                "help" => {
+                    if typed {
+                        editor.add_history(command)?;
+                    }
+                    print_interactive_help();
+                    continue;
+                }
+                _ => {}
+            }
         }
 
-        match input.to_lowercase().as_str() {
-            "exit" | "quit" | "\\q" => {
-                println!("{}", "👋 Goodbye!".bright_green());
-                break;
-            }
-            "help" | "\\h" => {
-                print_interactive_help();
-            }
-            "\\l" => {
-                // List databases
-                let databases = client.list_databases().await?;
-                let table = Table::new(&databases);
-                println!("{table}");
-            }
-            "\\d" => {
-                // List tables
-                let tables = client.list_tables(false).await?;
-                let table = Table::new(&tables);
-                println!("{table}");
-            }
-            _ => {
-                // Execute as SQL
-                match client.execute_query(input).await {
-                    Ok(rows) => {
-                        if rows.is_empty() {
-                            println!("{}", "Query executed successfully.".green());
-                        } else {
-                            display_rows_as_table(&rows);
+        // Backslash commands can be given in the middle of a statement, but
+        // not inside a quoted string or comment.
+        let in_statement = matches!(
+            splitter.pending(),
+            Pending::None | Pending::Statement | Pending::Parenthesis
+        );
+        if in_statement && command.starts_with('\\') {
+            if typed {
+                editor.add_history(command)?;
+            }
+
+            match MetaCommand::parse(command) {
+                Ok(MetaCommand::Quit) => {
+                    println!("{}", "👋 Goodbye!".bright_green());
+                    break;
+                }
+                Ok(MetaCommand::ResetBuffer) => {
+                    splitter.reset();
+                    entry.clear();
+                    println!("{}", "Query buffer reset (cleared).".dimmed());
+                }
+                Ok(MetaCommand::Edit) => {
+                    let text = if entry.is_empty() {
+                        &session.last_query
+                    } else {
+                        &entry
+                    };
+                    match tokio::task::block_in_place(|| edit_in_editor(text)) {
+                        Ok(edited) => {
+                            splitter.reset();
+                            entry.clear();
+                            editor.add_history(edited.trim_end())?;
+                            for line in edited.lines().rev() {
+                                session.queue.push_front(line.to_string());
+                            }
                         }
+                        Err(e) => eprintln!("{}", e.report().red()),
                     }
-                    Err(e) => {
-                        println!("{}", format!("Error: {e}").red());
+                }
+                Ok(meta) => {
+                    let canceller = session.client.canceller();
+                    if let Err(e) = canceller.run(session.run_meta(meta)).await {
+                        eprintln!("{}", e.report().red());
                     }
                 }
+                Err(e) => eprintln!("{}", e.report().red()),
+            }
+            continue;
+        }
+
+        if !entry.is_empty() {
+            entry.push('\n');
+        }
+        entry.push_str(&line);
+
+        let statements = splitter.push(&format!("{line}\n"));
+        if splitter.is_empty() {
+            if typed {
+                editor.add_history(&entry)?;
+            }
+            entry.clear();
+            // Drop any trailing comment so it does not prefix the next statement.
+            splitter.reset();
+        }
+
+        for statement in statements {
+            session.run_sql(&statement.text).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens `text` in the user's editor (`$EDITOR`, then `$VISUAL`, then `vi`)
+/// and returns the edited text.
+fn edit_in_editor(text: &str) -> Result<String, DconError> {
+    // A new file under an unguessable name, readable only by the user, so
+    // that nobody else can read the query or plant a link in its place.
+    let path = env::temp_dir().join(format!("dcon-edit-{}.sql", uuid::Uuid::new_v4()));
+    let context = || format!("Failed to edit '{}'", path.display());
+
+    let mut contents = text.to_string();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| DconError::io(e, context()))?;
+
+    let editor = env::var("EDITOR")
+        .or_else(|_| env::var("VISUAL"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // The variable may carry arguments, such as `code --wait`.
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .map_err(|e| DconError::io(e, format!("Failed to start editor '{editor}'")))?;
+
+    let edited = fs::read_to_string(&path).map_err(|e| DconError::io(e, context()));
+    let _ = fs::remove_file(&path);
+
+    if !status.success() {
+        return Err(DconError::UserInput(format!(
+            "Editor '{editor}' exited with {status}; the query buffer is unchanged"
+        )));
+    }
+
+    edited
+}
+
+/// Removes the terminal color codes added by `colored`, for output written to
+/// a file.
+fn strip_colors(text: &str) -> Cow<'_, str> {
+    static ESCAPE: OnceLock<Regex> = OnceLock::new();
+    ESCAPE
+        .get_or_init(|| Regex::new("\x1b\\[[0-9;]*m").expect("valid regex"))
+        .replace_all(text, "")
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// The prompt for the next line, showing what unfinished input is waiting
+/// for, as psql does.
+fn continuation_prompt(pending: Pending) -> &'static str {
+    match pending {
+        Pending::None => "postgres> ",
+        Pending::Statement => "postgres-> ",
+        Pending::SingleQuote => "postgres'> ",
+        Pending::DoubleQuote => "postgres\"> ",
+        Pending::DollarQuote => "postgres$> ",
+        Pending::BlockComment => "postgres*> ",
+        Pending::Parenthesis => "postgres(> ",
+    }
+}
+
+pub fn execute_profile_command(
+    command: &ProfileCommands,
+    format: &OutputFormat,
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
+    let mut config = load_config()?;
+
+    match command {
+        ProfileCommands::List => {
+            if config.profiles.is_empty() {
+                status!("{}", "No profiles configured.".yellow());
+                return Ok(());
             }
+
+            let profiles: Vec<ProfileInfo> = config
+                .profiles
+                .iter()
+                .map(|(name, profile)| ProfileInfo {
+                    name: name.clone(),
+                    host: profile.host.clone().unwrap_or_default(),
+                    port: profile.port.map(|p| p.to_string()).unwrap_or_default(),
+                    user: profile.user.clone().unwrap_or_default(),
+                    database: profile.database.clone().unwrap_or_default(),
+                    is_default: if config.default_profile.as_deref() == Some(name) {
+                        "YES".to_string()
+                    } else {
+                        "NO".to_string()
+                    },
+                })
+                .collect();
+
+            print_records(
+                &Records::from_items("profiles", &profiles)?,
+                format,
+                display,
+            )?;
+        }
+
+        ProfileCommands::Show { name } => {
+            let profile = config
+                .profiles
+                .get(name)
+                .ok_or_else(|| DconError::UserInput(format!("Profile '{name}' not found")))?;
+
+            let settings: Vec<ConnectionInfo> = profile
+                .settings()
+                .into_iter()
+                .map(|(property, value)| ConnectionInfo {
+                    property: property.to_string(),
+                    value,
+                })
+                .collect();
+
+            status!("{}", format!("👤 Profile: {name}").bright_green().bold());
+            let sections = [Records::from_items("profile_settings", &settings)?];
+            print_document(profile, &sections, format, display)?;
+        }
+
+        ProfileCommands::Add {
+            name,
+            host,
+            port,
+            user,
+            database,
+            sslmode,
+            sslrootcert,
+            sslcert,
+            sslkey,
+            schema,
+            search_path,
+            format: profile_format,
+            no_color,
+            default,
+            force,
+        } => {
+            if config.profiles.contains_key(name) && !force {
+                return Err(DconError::UserInput(format!(
+                    "Profile '{name}' already exists (use --force to replace it)"
+                )));
+            }
+
+            let profile = Profile {
+                host: host.clone(),
+                port: *port,
+                user: user.clone(),
+                database: database.clone(),
+                password: None,
+                sslmode: *sslmode,
+                sslrootcert: sslrootcert.clone(),
+                sslcert: sslcert.clone(),
+                sslkey: sslkey.clone(),
+                schema: schema.clone(),
+                search_path: search_path.clone(),
+                format: profile_format.clone(),
+                color: no_color.then_some(false),
+            };
+
+            config.profiles.insert(name.clone(), profile);
+            if *default {
+                config.default_profile = Some(name.clone());
+            }
+
+            let path = save_config(&config)?;
+            status!(
+                "{}",
+                format!("✅ Profile '{name}' saved to {}", path.display()).green()
+            );
+        }
+
+        ProfileCommands::Remove { name } => {
+            if config.profiles.remove(name).is_none() {
+                return Err(DconError::UserInput(format!("Profile '{name}' not found")));
+            }
+            if config.default_profile.as_deref() == Some(name.as_str()) {
+                config.default_profile = None;
+            }
+
+            save_config(&config)?;
+            status!("{}", format!("✅ Profile '{name}' removed").green());
         }
     }
 
     Ok(())
 }
 
-fn confirm_action(message: &str) -> Result<bool, Box<dyn Error>> {
-    print!("{message} (y/N): ");
-    io::stdout().flush()?;
+fn confirm_action(message: &str) -> Result<bool, DconError> {
+    eprint!("{message} (y/N): ");
+    io::stderr().flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
@@ -428,12 +1135,62 @@ fn confirm_action(message: &str) -> Result<bool, Box<dyn Error>> {
 }
 
 fn print_interactive_help() {
-    println!("{}", "Available commands:".bright_cyan().bold());
-    println!("  {}  List databases", "\\l".bright_yellow());
-    println!("  {}  List tables", "\\d".bright_yellow());
-    println!("  {}  Show help", "\\h".bright_yellow());
-    println!("  {}  Exit", "\\q".bright_yellow());
-    println!("  {}  Execute any SQL query", "SQL".bright_yellow());
+    let help = [
+        ("General", ""),
+        ("\\?, \\h, help", "Show this help"),
+        ("\\q, exit", "Quit"),
+        ("", ""),
+        ("Query Buffer", ""),
+        (
+            "\\e",
+            "Edit the query buffer (or the last query) in $EDITOR",
+        ),
+        ("\\r", "Reset the query buffer"),
+        ("\\i FILE", "Execute commands from a file"),
+        ("\\o [FILE]", "Send results to a file, or back to stdout"),
+        ("", ""),
+        ("Informational", ""),
+        ("\\l", "List databases"),
+        ("\\d", "List tables"),
+        ("\\d NAME", "Describe a table, view or sequence"),
+        ("\\dt [PATTERN]", "List tables"),
+        ("\\dv [PATTERN]", "List views"),
+        ("\\di [PATTERN]", "List indexes"),
+        ("\\ds [PATTERN]", "List sequences"),
+        ("\\df [PATTERN]", "List functions"),
+        ("\\dn [PATTERN]", "List schemas"),
+        ("\\du [PATTERN]", "List roles"),
+        ("\\dx [PATTERN]", "List extensions"),
+        ("", ""),
+        ("Connection", ""),
+        ("\\c [DB [USER]]", "Connect to another database"),
+        ("\\conninfo", "Show the current connection"),
+        ("", ""),
+        ("Formatting", ""),
+        ("\\x [on|off|auto]", "Toggle expanded output"),
+        ("\\pset pager [on|off]", "Toggle the pager for long output"),
+        ("\\timing [on|off]", "Toggle timing of statements"),
+        ("", ""),
+        ("Variables", ""),
+        ("\\set [NAME VALUE]", "Set a variable, or list them all"),
+        ("\\unset NAME", "Unset a variable"),
+    ];
+
+    for (command, description) in help {
+        match (command, description) {
+            ("", _) => println!(),
+            (title, "") => println!("{}", title.bright_cyan().bold()),
+            (command, description) => {
+                println!(
+                    "  {}  {description}",
+                    format!("{command:<18}").bright_yellow()
+                );
+            }
+        }
+    }
+    println!();
+    println!("SQL statements end with ';' and may span several lines. PATTERN may use");
+    println!("* and ? wildcards and a schema prefix, as in psql: \\dt public.user*");
     println!();
     println!("Examples:");
     println!("  SELECT * FROM users LIMIT 5;");
@@ -441,81 +1198,116 @@ fn print_interactive_help() {
     println!("  UPDATE users SET name = 'Jane' WHERE id = 1;");
 }
 
-use tokio_postgres::Row;
+/// Prints query results as the client hands them over: all at once through
+/// the pager, or with `--fetch-count` batch by batch straight to stdout.
+struct ResultPrinter<'a> {
+    /// Table the `sql-insert` statements insert into
+    table: &'a str,
+    format: &'a OutputFormat,
+    display: &'a DisplayOptions,
+    /// `\o` file receiving the output instead of stdout
+    output: Option<&'a mut File>,
+    /// Set up by the first batch when streaming
+    writer: Option<Box<dyn RowWriter>>,
+    /// Rows printed so far
+    rows: usize,
+}
 
-fn display_rows_as_table(rows: &[Row]) {
-    if rows.is_empty() {
-        return;
+impl<'a> ResultPrinter<'a> {
+    fn new(table: &'a str, format: &'a OutputFormat, display: &'a DisplayOptions) -> Self {
+        ResultPrinter {
+            table,
+            format,
+            display,
+            output: None,
+            writer: None,
+            rows: 0,
+        }
     }
 
-    // Get column names
-    let columns: Vec<String> = rows[0]
-        .columns()
-        .iter()
-        .map(|col| col.name().to_string())
-        .collect();
+    fn with_output(mut self, output: Option<&'a mut File>) -> Self {
+        self.output = output;
+        self
+    }
 
-    // Print header
-    println!("{}", columns.join(" | ").bright_blue().bold());
-    println!("{}", "-".repeat(columns.len() * 15).dimmed());
+    fn print(&mut self, columns: &[Column], rows: Vec<Row>) -> Result<(), DconError> {
+        let records = Records::from_columns(self.table, columns, &rows);
+        self.rows += rows.len();
 
-    // Print rows
-    for row in rows {
-        let mut values = Vec::new();
-        for (i, _col) in columns.iter().enumerate() {
-            let value = get_column_value(row, i);
-            values.push(value);
+        if self.display.fetch_count(self.format) == 0 {
+            let text = formatter(self.format, self.display).records(&records)?;
+            return self.write(&text, true);
         }
-        println!("{}", values.join(" | "));
+        let writer = self
+            .writer
+            .get_or_insert_with(|| formatter(self.format, self.display).row_writer());
+        let text = writer.rows(&records)?;
+        self.write(&text, false)
     }
 
-    println!();
-    println!("{}", format!("({} rows)", rows.len()).dimmed());
-}
-
-fn display_rows_as_csv(rows: &[Row]) {
-    if rows.is_empty() {
-        return;
+    /// Ends the output once every row has been printed. A result without
+    /// rows still shows its columns.
+    fn finish(mut self, columns: &[Column]) -> Result<(), DconError> {
+        if self.rows == 0 {
+            self.print(columns, Vec::new())?;
+        }
+        match self.writer.as_mut().map(|writer| writer.finish()) {
+            Some(text) => self.write(&text?, false),
+            None => Ok(()),
+        }
     }
 
-    // Get column names
-    let columns: Vec<String> = rows[0]
-        .columns()
-        .iter()
-        .map(|col| col.name().to_string())
-        .collect();
-
-    // Print header
-    println!("{}", columns.join(","));
-
-    // Print rows
-    for row in rows {
-        let mut values = Vec::new();
-        for (i, _col) in columns.iter().enumerate() {
-            let value = get_column_value(row, i);
-            values.push(format!("\"{}\"", value.replace("\"", "\"\"")));
+    /// Writes the whole result (`whole`), which is paged on a terminal, or
+    /// part of a streamed one.
+    fn write(&mut self, text: &str, whole: bool) -> Result<(), DconError> {
+        match &mut self.output {
+            Some(file) => file
+                .write_all(strip_colors(text).as_bytes())
+                .map_err(|e| DconError::io(e, "Failed to write output")),
+            None if whole => pager::page(text, self.display.pager),
+            None => pager::print(text),
         }
-        println!("{}", values.join(","));
     }
 }
 
-fn rows_to_json(rows: &[Row]) -> Result<Value, Box<dyn Error>> {
-    let mut json_rows = Vec::new();
-
-    for row in rows {
-        let mut json_row = serde_json::Map::new();
-
-        for (i, column) in row.columns().iter().enumerate() {
-            let value = get_column_value(row, i);
-            json_row.insert(column.name().to_string(), Value::String(value));
-        }
+fn print_records(
+    records: &Records,
+    format: &OutputFormat,
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
+    let text = formatter(format, display).records(records)?;
+    pager::page(&text, display.pager)
+}
 
-        json_rows.push(Value::Object(json_row));
-    }
+/// Prints a document made of several lists, such as a table description, in
+/// the selected format.
+fn print_document<T: Serialize>(
+    document: &T,
+    sections: &[Records],
+    format: &OutputFormat,
+    display: &DisplayOptions,
+) -> Result<(), DconError> {
+    let document = serde_json::to_value(document)?;
+    let text = formatter(format, display).document(&document, sections)?;
+    pager::page(&text, display.pager)
+}
 
-    Ok(Value::Array(json_rows))
+/// A table description in table format, as shown by `\d name`.
+fn table_details(details: &TableDetails) -> Result<String, DconError> {
+    formatter(&OutputFormat::Table, &DisplayOptions::default())
+        .document(&Value::Null, &table_details_sections(details)?)
 }
 
-fn get_column_value(row: &Row, index: usize) -> String {
-    PostgresClient::get_column_value(row, index)
+fn table_details_sections(details: &TableDetails) -> Result<Vec<Records>, DconError> {
+    Ok(vec![
+        Records::from_items("table_properties", &details.properties())?,
+        Records::from_items("columns", &details.columns)?.titled("Columns"),
+        Records::from_items("indexes", &details.indexes)?.titled("Indexes"),
+        Records::from_items("constraints", &details.constraints)?.titled("Constraints"),
+        Records::from_items("foreign_keys", &details.foreign_keys)?.titled("Foreign Keys"),
+        Records::from_items("referenced_by", &details.referenced_by)?.titled("Referenced By"),
+        Records::from_items("triggers", &details.triggers)?.titled("Triggers"),
+        Records::from_items("policies", &details.policies)?.titled("Policies"),
+        Records::from_items("partitions", &details.partitions)?.titled("Partitions"),
+    ])
 }
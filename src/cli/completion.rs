@@ -0,0 +1,593 @@
+use crate::models::catalog::{CatalogNames, CatalogObject};
+use crate::models::table::{display_ident, quote_ident, QualifiedName};
+use regex::Regex;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Backslash commands understood by interactive mode.
+pub const BACKSLASH_COMMANDS: &[&str] = &[
+    "\\?",
+    "\\c",
+    "\\conninfo",
+    "\\d",
+    "\\df",
+    "\\di",
+    "\\dn",
+    "\\ds",
+    "\\dt",
+    "\\du",
+    "\\dv",
+    "\\dx",
+    "\\e",
+    "\\h",
+    "\\i",
+    "\\l",
+    "\\o",
+    "\\pset",
+    "\\q",
+    "\\r",
+    "\\set",
+    "\\timing",
+    "\\unset",
+    "\\x",
+];
+
+/// SQL keywords offered for completion.
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "ANALYZE",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLUMN",
+    "COMMENT",
+    "COMMIT",
+    "CONSTRAINT",
+    "COPY",
+    "CREATE",
+    "CROSS",
+    "CURRENT_DATE",
+    "CURRENT_TIMESTAMP",
+    "CURRENT_USER",
+    "DATABASE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "EXPLAIN",
+    "EXTENSION",
+    "FALSE",
+    "FETCH",
+    "FILTER",
+    "FIRST",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "FUNCTION",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "ILIKE",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LATERAL",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATERIALIZED",
+    "NOT",
+    "NOTHING",
+    "NULL",
+    "NULLS",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PRIMARY",
+    "PROCEDURE",
+    "REFERENCES",
+    "REFRESH",
+    "RENAME",
+    "REPLACE",
+    "RESET",
+    "RETURNING",
+    "REVOKE",
+    "RIGHT",
+    "ROLE",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "SCHEMA",
+    "SELECT",
+    "SEQUENCE",
+    "SET",
+    "SHOW",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRIGGER",
+    "TRUE",
+    "TRUNCATE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+];
+
+/// Keywords that are followed by a relation name.
+const RELATION_KEYWORDS: &[&str] = &["FROM", "JOIN", "UPDATE", "INTO", "TABLE", "TRUNCATE"];
+
+/// Keywords that are followed by an expression, where column names fit.
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "WHERE",
+    "AND",
+    "OR",
+    "NOT",
+    "ON",
+    "BY",
+    "SET",
+    "HAVING",
+    "RETURNING",
+    "DISTINCT",
+    "WHEN",
+    "THEN",
+    "ELSE",
+    "CASE",
+];
+
+/// Tab completion for interactive mode.
+///
+/// Candidates depend on the text before the cursor: backslash commands at the
+/// start of a line, databases after `\c`, relations after `FROM`, `JOIN` and
+/// the like, columns of the relations the statement mentions where an
+/// expression is expected, the members of a schema after `schema.` and the
+/// columns of a table (or alias) after `table.`. Keywords fill in elsewhere.
+#[derive(Default)]
+pub struct SqlHelper {
+    catalog: CatalogNames,
+    /// Earlier lines of the statement being entered
+    buffer: String,
+    /// File names after `\i` and `\o`
+    files: FilenameCompleter,
+}
+
+impl SqlHelper {
+    pub fn set_catalog(&mut self, catalog: CatalogNames) {
+        self.catalog = catalog;
+    }
+
+    pub fn set_buffer(&mut self, buffer: &str) {
+        self.buffer = buffer.to_string();
+    }
+
+    /// Start of the word being completed and the candidates for it.
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '"' | '\\'))
+            .last()
+            .map_or(pos, |(i, _)| i);
+        let word = &before[start..];
+
+        if self.buffer.trim().is_empty() && before.trim_start().starts_with('\\') {
+            return (start, self.command_candidates(before, start, word));
+        }
+
+        if let Some(dot) = word.rfind('.') {
+            let qualifier = &word[..dot];
+            let partial = &word[dot + 1..];
+            let text = format!("{}\n{line}", self.buffer);
+            return (
+                start + dot + 1,
+                self.member_candidates(qualifier, partial, &tokens(&text)),
+            );
+        }
+
+        let before_word = tokens(&format!("{}\n{}", self.buffer, &before[..start]));
+        let all = tokens(&format!("{}\n{line}", self.buffer));
+        let previous = before_word.last().map(|token| token.to_uppercase());
+
+        let candidates = match previous.as_deref() {
+            None => keywords(word),
+            Some(keyword) if RELATION_KEYWORDS.contains(&keyword) => self.relation_candidates(word),
+            Some(",") if clause(&before_word) == Some("FROM") => self.relation_candidates(word),
+            Some(token)
+                if EXPRESSION_KEYWORDS.contains(&token)
+                    || (is_operator(token) && clause(&before_word) != Some("FROM")) =>
+            {
+                let mut candidates = self.column_candidates(word, &all);
+                candidates.extend(self.function_candidates(word));
+                candidates.extend(keywords(word));
+                candidates
+            }
+            _ => keywords(word),
+        };
+
+        (start, dedup(candidates))
+    }
+
+    fn command_candidates(&self, before: &str, start: usize, word: &str) -> Vec<String> {
+        let command_start = before.len() - before.trim_start().len();
+        if start == command_start {
+            return BACKSLASH_COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(word))
+                .map(|command| command.to_string())
+                .collect();
+        }
+
+        let command = before.split_whitespace().next().unwrap_or_default();
+        match command {
+            "\\c" | "\\connect" => self
+                .catalog
+                .databases
+                .iter()
+                .filter(|name| ident_matches(name, word))
+                .map(|name| display_ident(name))
+                .collect(),
+            "\\df" => self.function_candidates(word),
+            "\\dn" => self
+                .catalog
+                .schemas
+                .iter()
+                .filter(|schema| ident_matches(schema, word))
+                .map(|schema| display_ident(schema))
+                .collect(),
+            "\\d" | "\\dt" | "\\dv" | "\\di" | "\\ds" => self.relation_candidates(word),
+            "\\pset" => ["expanded", "pager"]
+                .iter()
+                .filter(|option| option.starts_with(word))
+                .map(|option| option.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Relations that can be named without a schema, and schemas.
+    fn relation_candidates(&self, partial: &str) -> Vec<String> {
+        let relations = self
+            .catalog
+            .relations
+            .iter()
+            .filter(|relation| relation.visible && ident_matches(&relation.name, partial))
+            .map(|relation| display_ident(&relation.name));
+        let schemas = self
+            .catalog
+            .schemas
+            .iter()
+            .filter(|schema| ident_matches(schema, partial))
+            .map(|schema| format!("{}.", display_ident(schema)));
+
+        relations.chain(schemas).collect()
+    }
+
+    /// Columns of the relations referenced in the statement.
+    fn column_candidates(&self, partial: &str, tokens: &[String]) -> Vec<String> {
+        referenced_relations(tokens)
+            .iter()
+            .filter_map(|(name, _)| self.find_relation(name))
+            .flat_map(|relation| &relation.columns)
+            .filter(|column| ident_matches(column, partial))
+            .map(|column| display_ident(column))
+            .collect()
+    }
+
+    fn function_candidates(&self, partial: &str) -> Vec<String> {
+        self.catalog
+            .functions
+            .iter()
+            .filter(|function| function.visible && ident_matches(&function.name, partial))
+            .map(|function| format!("{}(", display_ident(&function.name)))
+            .collect()
+    }
+
+    /// Candidates after `qualifier.`: the columns of a relation or alias, or
+    /// the relations and functions of a schema.
+    fn member_candidates(&self, qualifier: &str, partial: &str, tokens: &[String]) -> Vec<String> {
+        let Ok(name) = qualifier.parse::<QualifiedName>() else {
+            return Vec::new();
+        };
+
+        let alias = name.schema.is_none().then(|| {
+            referenced_relations(tokens)
+                .into_iter()
+                .find(|(_, alias)| alias.as_deref() == Some(name.name.as_str()))
+                .map(|(relation, _)| relation)
+        });
+        let relation = alias
+            .flatten()
+            .and_then(|relation| self.find_relation(&relation))
+            .or_else(|| self.find_relation(&name));
+
+        let mut candidates: Vec<String> = match relation {
+            Some(relation) => relation
+                .columns
+                .iter()
+                .filter(|column| ident_matches(column, partial))
+                .map(|column| display_ident(column))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        if name.schema.is_none() {
+            let in_schema = |object: &&CatalogObject| {
+                object.schema == name.name && ident_matches(&object.name, partial)
+            };
+            candidates.extend(
+                self.catalog
+                    .relations
+                    .iter()
+                    .filter(in_schema)
+                    .map(|relation| display_ident(&relation.name)),
+            );
+            candidates.extend(
+                self.catalog
+                    .functions
+                    .iter()
+                    .filter(in_schema)
+                    .map(|function| format!("{}(", display_ident(&function.name))),
+            );
+        }
+
+        dedup(candidates)
+    }
+
+    /// The relation a name refers to, following the search_path for
+    /// unqualified names.
+    fn find_relation(&self, name: &QualifiedName) -> Option<&CatalogObject> {
+        let relations = self.catalog.relations.iter();
+        match &name.schema {
+            Some(schema) => relations
+                .filter(|relation| &relation.schema == schema)
+                .find(|relation| relation.name == name.name),
+            None => relations
+                .filter(|relation| relation.visible)
+                .find(|relation| relation.name == name.name),
+        }
+    }
+}
+
+impl Completer for SqlHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        if self.buffer.trim().is_empty() && is_file_command(&line[..pos]) {
+            return self.files.complete_path(line, pos);
+        }
+
+        let (start, candidates) = self.candidates(line, pos);
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for SqlHelper {
+    type Hint = String;
+}
+
+impl Highlighter for SqlHelper {}
+
+impl Validator for SqlHelper {}
+
+impl Helper for SqlHelper {}
+
+/// Whether a statement may add, drop or rename objects, or change which of
+/// them are visible, so the names used for completion should be reloaded.
+pub fn changes_catalog(sql: &str) -> bool {
+    let tokens = tokens(sql);
+    match tokens.first().map(|token| token.to_uppercase()).as_deref() {
+        Some("CREATE" | "ALTER" | "DROP" | "IMPORT" | "ROLLBACK") => true,
+        Some("SET" | "RESET") => tokens.iter().any(|t| t.eq_ignore_ascii_case("search_path")),
+        _ => false,
+    }
+}
+
+/// Splits SQL into identifiers (schema-qualified names stay together),
+/// string literals and single punctuation characters, dropping comments.
+fn tokens(sql: &str) -> Vec<String> {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token = TOKEN.get_or_init(|| {
+        let ident = r#"(?:"(?:[^"]|"")*"|[A-Za-z_][A-Za-z0-9_$]*)"#;
+        Regex::new(&format!(
+            r"--[^\n]*|/\*(?s:.)*?\*/|'(?:[^']|'')*'|{ident}(?:\.{ident})*|\S"
+        ))
+        .expect("valid regex")
+    });
+
+    token
+        .find_iter(sql)
+        .map(|m| m.as_str())
+        .filter(|token| !token.starts_with("--") && !token.starts_with("/*"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Relations named after `FROM`, `JOIN`, `UPDATE` and `INTO`, with their
+/// aliases.
+fn referenced_relations(tokens: &[String]) -> Vec<(QualifiedName, Option<String>)> {
+    let mut relations = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let keyword = tokens[i].to_uppercase();
+        i += 1;
+        if !matches!(keyword.as_str(), "FROM" | "JOIN" | "UPDATE" | "INTO") {
+            continue;
+        }
+
+        loop {
+            if tokens
+                .get(i)
+                .is_some_and(|token| token.eq_ignore_ascii_case("ONLY"))
+            {
+                i += 1;
+            }
+            let Some(name) = tokens
+                .get(i)
+                .filter(|token| is_identifier(token))
+                .and_then(|token| token.parse::<QualifiedName>().ok())
+            else {
+                break;
+            };
+            i += 1;
+
+            if tokens
+                .get(i)
+                .is_some_and(|token| token.eq_ignore_ascii_case("AS"))
+            {
+                i += 1;
+            }
+            let alias = tokens
+                .get(i)
+                .filter(|token| is_identifier(token) && !is_keyword(token))
+                .and_then(|token| token.parse::<QualifiedName>().ok())
+                .filter(|alias| alias.schema.is_none())
+                .map(|alias| alias.name);
+            if alias.is_some() {
+                i += 1;
+            }
+            relations.push((name, alias));
+
+            // FROM a, b
+            if keyword == "FROM" && tokens.get(i).is_some_and(|token| token == ",") {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    relations
+}
+
+/// The last clause keyword before the cursor, telling a comma in a `FROM`
+/// list from one in a select list.
+fn clause(tokens: &[String]) -> Option<&'static str> {
+    tokens.iter().rev().find_map(|token| {
+        let token = token.to_uppercase();
+        [
+            "FROM",
+            "SELECT",
+            "WHERE",
+            "BY",
+            "SET",
+            "HAVING",
+            "RETURNING",
+            "VALUES",
+        ]
+        .into_iter()
+        .find(|keyword| *keyword == token)
+    })
+}
+
+fn keywords(partial: &str) -> Vec<String> {
+    // Follow the case the user started typing in, upper case by default.
+    let lower = partial.chars().any(|c| c.is_lowercase());
+    KEYWORDS
+        .iter()
+        .filter(|keyword| keyword.starts_with(&partial.to_uppercase()))
+        .map(|keyword| {
+            if lower {
+                keyword.to_lowercase()
+            } else {
+                keyword.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Whether `name` completes `partial`, which may be typed in any case or
+/// start with a double quote.
+fn ident_matches(name: &str, partial: &str) -> bool {
+    if partial.starts_with('"') {
+        quote_ident(name).starts_with(partial)
+    } else {
+        name.to_lowercase().starts_with(&partial.to_lowercase())
+    }
+}
+
+/// Whether the cursor is in the file argument of `\i` or `\o`.
+fn is_file_command(before: &str) -> bool {
+    let before = before.trim_start();
+    matches!(before.split_whitespace().next(), Some("\\i" | "\\o"))
+        && before.contains(char::is_whitespace)
+}
+
+fn is_identifier(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '"')
+}
+
+fn is_keyword(token: &str) -> bool {
+    KEYWORDS.contains(&token.to_uppercase().as_str())
+}
+
+fn is_operator(token: &str) -> bool {
+    matches!(
+        token,
+        "=" | "<" | ">" | "(" | "," | "+" | "-" | "*" | "/" | "%" | "|" | "!"
+    )
+}
+
+/// Drops repeated candidates, keeping the first occurrence.
+fn dedup(candidates: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|candidate| seen.insert(candidate.clone()))
+        .collect()
+}
@@ -0,0 +1,295 @@
+use crate::error::DconError;
+
+/// Line terminator written after each CSV record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    /// As RFC 4180 specifies, and as spreadsheet tools on Windows expect
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "lf"),
+            LineEnding::Crlf => write!(f, "crlf"),
+        }
+    }
+}
+
+impl std::str::FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            _ => Err(format!("Invalid line ending: {s} (expected lf or crlf)")),
+        }
+    }
+}
+
+/// The dialect of CSV output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub quote: char,
+    /// Write the column names as the first record
+    pub header: bool,
+    /// Text written for NULL
+    pub null: String,
+    pub line_ending: LineEnding,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            quote: '"',
+            header: true,
+            null: String::new(),
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Rejects dialects whose output could not be read back unambiguously.
+    pub fn validate(&self) -> Result<(), DconError> {
+        if self.delimiter == self.quote {
+            return Err(DconError::UserInput(
+                "CSV delimiter and quote character must differ".to_string(),
+            ));
+        }
+        if [self.delimiter, self.quote]
+            .iter()
+            .any(|c| matches!(c, '\r' | '\n'))
+        {
+            return Err(DconError::UserInput(
+                "CSV delimiter and quote character cannot be line breaks".to_string(),
+            ));
+        }
+        if self.null.contains([self.delimiter, self.quote, '\r', '\n']) {
+            return Err(DconError::UserInput(
+                "CSV NULL text cannot contain the delimiter, the quote character or line breaks"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a delimiter or quote character given on the command line; `\t` and
+/// `tab` stand for a tab.
+pub fn parse_char(s: &str) -> Result<char, String> {
+    if s == "\\t" || s.eq_ignore_ascii_case("tab") {
+        return Ok('\t');
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("expected a single character, got \"{s}\"")),
+    }
+}
+
+/// Builds CSV text as RFC 4180 describes it, in the configured dialect.
+///
+/// A value is quoted when it contains the delimiter, the quote character or
+/// a line break, with quote characters doubled. NULL is written as the NULL
+/// text without quotes, and a value equal to the NULL text (such as an empty
+/// string, by default) is quoted so that the two can be told apart.
+pub struct CsvWriter<'a> {
+    options: &'a CsvOptions,
+    text: String,
+}
+
+impl<'a> CsvWriter<'a> {
+    pub fn new(options: &'a CsvOptions) -> Self {
+        CsvWriter {
+            options,
+            text: String::new(),
+        }
+    }
+
+    /// Appends a record; `None` fields are NULL.
+    pub fn record<'v>(&mut self, fields: impl IntoIterator<Item = Option<&'v str>>) {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                self.text.push(self.options.delimiter);
+            }
+            match field {
+                None => self.text.push_str(&self.options.null),
+                Some(value) => self.field(value),
+            }
+        }
+        self.text.push_str(self.options.line_ending.as_str());
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+
+    fn field(&mut self, value: &str) {
+        let quote = self.options.quote;
+        let needs_quotes = value == self.options.null
+            || value.contains([self.options.delimiter, quote, '\r', '\n']);
+
+        if !needs_quotes {
+            self.text.push_str(value);
+            return;
+        }
+
+        self.text.push(quote);
+        for c in value.chars() {
+            if c == quote {
+                self.text.push(quote);
+            }
+            self.text.push(c);
+        }
+        self.text.push(quote);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(options: &CsvOptions, records: &[&[Option<&str>]]) -> String {
+        let mut writer = CsvWriter::new(options);
+        for record in records {
+            writer.record(record.iter().copied());
+        }
+        writer.finish()
+    }
+
+    #[test]
+    fn quotes_only_when_needed() {
+        let options = CsvOptions::default();
+        let cases = [
+            ("plain", "plain"),
+            ("a,b", r#""a,b""#),
+            (r#"say "hi""#, r#""say ""hi""""#),
+            ("two\nlines", "\"two\nlines\""),
+            ("cr\r", "\"cr\r\""),
+            (" padded ", " padded "),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(write(&options, &[&[Some(value)]]), format!("{expected}\n"));
+        }
+    }
+
+    #[test]
+    fn null_and_values_equal_to_the_null_text() {
+        let options = CsvOptions::default();
+        assert_eq!(
+            write(&options, &[&[None, Some(""), Some("x")]]),
+            ",\"\",x\n"
+        );
+
+        let options = CsvOptions {
+            null: "NULL".to_string(),
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            write(&options, &[&[None, Some("NULL"), Some("")]]),
+            "NULL,\"NULL\",\n"
+        );
+    }
+
+    #[test]
+    fn dialects() {
+        let options = CsvOptions {
+            delimiter: ';',
+            quote: '\'',
+            line_ending: LineEnding::Crlf,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            write(
+                &options,
+                &[
+                    &[Some("a;b"), Some("it's"), Some("a,b")],
+                    &[None, Some("x")]
+                ]
+            ),
+            "'a;b';'it''s';a,b\r\n;x\r\n"
+        );
+
+        let options = CsvOptions {
+            delimiter: '\t',
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            write(&options, &[&[Some("a\tb"), Some("c")]]),
+            "\"a\tb\"\tc\n"
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let valid = CsvOptions::default();
+        assert!(valid.validate().is_ok());
+
+        let invalid = [
+            CsvOptions {
+                delimiter: '"',
+                ..CsvOptions::default()
+            },
+            CsvOptions {
+                delimiter: '\n',
+                ..CsvOptions::default()
+            },
+            CsvOptions {
+                quote: '\r',
+                ..CsvOptions::default()
+            },
+            CsvOptions {
+                null: "a,b".to_string(),
+                ..CsvOptions::default()
+            },
+            CsvOptions {
+                null: "\"".to_string(),
+                ..CsvOptions::default()
+            },
+            CsvOptions {
+                null: "\n".to_string(),
+                ..CsvOptions::default()
+            },
+        ];
+        for options in invalid {
+            assert!(
+                matches!(options.validate(), Err(DconError::UserInput(_))),
+                "{options:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_characters() {
+        assert_eq!(parse_char(";"), Ok(';'));
+        assert_eq!(parse_char("\\t"), Ok('\t'));
+        assert_eq!(parse_char("TAB"), Ok('\t'));
+        assert_eq!(parse_char("é"), Ok('é'));
+        assert!(parse_char("").is_err());
+        assert!(parse_char(";;").is_err());
+    }
+
+    #[test]
+    fn parses_line_endings() {
+        assert_eq!("CRLF".parse(), Ok(LineEnding::Crlf));
+        assert_eq!("lf".parse(), Ok(LineEnding::Lf));
+        assert!("cr".parse::<LineEnding>().is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::cli::output::Records;
+use crate::cli::ExpandedMode;
+use colored::*;
+use console::{measure_text_width, Term};
+
+/// Query results laid out as text, either as psql's aligned grid or, in
+/// expanded mode, as one block of `column | value` lines per record.
+///
+/// Widths are measured in terminal columns, so wide (CJK) and combining
+/// characters line up. Values spanning several lines are continued on the
+/// next line, with a `+` marking the break as in psql.
+pub struct Grid {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// Columns whose values are right-aligned, such as numbers
+    right_aligned: Vec<bool>,
+}
+
+impl Grid {
+    /// Lays out query results; NULLs are shown as `NULL`.
+    pub fn from_records(records: &Records) -> Self {
+        Grid {
+            headers: records.headers.clone(),
+            rows: records
+                .rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|value| value.clone().unwrap_or_else(|| "NULL".to_string()))
+                        .collect()
+                })
+                .collect(),
+            right_aligned: records.numeric.clone(),
+        }
+    }
+
+    /// Renders the grid, or the expanded layout when `mode` asks for it. In
+    /// `auto` mode the expanded layout is used when the grid is wider than the
+    /// terminal.
+    pub fn render(&self, mode: ExpandedMode) -> String {
+        let expanded = match mode {
+            ExpandedMode::Off => false,
+            ExpandedMode::On => true,
+            ExpandedMode::Auto => {
+                terminal_width().is_some_and(|width| self.aligned_width() > width)
+            }
+        };
+
+        if expanded {
+            self.expanded()
+        } else {
+            self.aligned()
+        }
+    }
+
+    fn column_widths(&self) -> Vec<usize> {
+        self.headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                self.rows
+                    .iter()
+                    .flat_map(|row| row[i].lines())
+                    .map(measure_text_width)
+                    .chain([measure_text_width(header)])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn aligned_width(&self) -> usize {
+        let widths = self.column_widths();
+        widths
+            .iter()
+            .map(|width| width + 3)
+            .sum::<usize>()
+            .saturating_sub(1)
+    }
+
+    fn aligned(&self) -> String {
+        let widths = self.column_widths();
+        let mut text = self.aligned_header(&widths);
+        text.push_str(&self.aligned_rows(&widths));
+        text.push_str(&format!("{}\n", row_count(self.rows.len()).dimmed()));
+        text
+    }
+
+    fn aligned_header(&self, widths: &[usize]) -> String {
+        let mut text = String::new();
+
+        let header: Vec<String> = self
+            .headers
+            .iter()
+            .zip(widths)
+            .map(|(header, &width)| format!(" {} ", center(header, width)))
+            .collect();
+        text.push_str(&format!(
+            "{}\n",
+            header.join("|").trim_end().bright_blue().bold()
+        ));
+
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+        text.push_str(&format!("{}\n", rule.join("+").dimmed()));
+        text
+    }
+
+    fn aligned_rows(&self, widths: &[usize]) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            let cells: Vec<Vec<&str>> = row.iter().map(|value| cell_lines(value)).collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+            for line in 0..height {
+                let parts: Vec<String> = cells
+                    .iter()
+                    .enumerate()
+                    .map(|(i, lines)| {
+                        let value = lines.get(line).copied().unwrap_or("");
+                        let padded = if self.right_aligned[i] {
+                            pad_left(value, widths[i])
+                        } else {
+                            pad_right(value, widths[i])
+                        };
+                        let marker = if line + 1 < lines.len() { '+' } else { ' ' };
+                        format!(" {padded}{marker}")
+                    })
+                    .collect();
+                text.push_str(&format!("{}\n", parts.join("|").trim_end()));
+            }
+        }
+        text
+    }
+
+    fn name_width(&self) -> usize {
+        self.headers
+            .iter()
+            .map(|header| measure_text_width(header))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn value_width(&self) -> usize {
+        self.rows
+            .iter()
+            .flatten()
+            .flat_map(|value| value.lines())
+            .map(measure_text_width)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn expanded(&self) -> String {
+        self.expanded_records(1, self.name_width(), self.value_width())
+    }
+
+    /// The expanded layout, numbering records from `first`.
+    fn expanded_records(&self, first: usize, name_width: usize, value_width: usize) -> String {
+        let mut text = String::new();
+        for (n, row) in self.rows.iter().enumerate() {
+            let title = format!("-[ RECORD {} ]", first + n);
+            let fill = (name_width + 1).saturating_sub(measure_text_width(&title));
+            let title = format!(
+                "{title}{}+{}",
+                "-".repeat(fill),
+                "-".repeat(value_width + 1)
+            );
+            text.push_str(&format!("{}\n", title.bright_blue().bold()));
+
+            for (header, value) in self.headers.iter().zip(row) {
+                let lines = cell_lines(value);
+                for (i, line) in lines.iter().enumerate() {
+                    let name = if i == 0 { header.as_str() } else { "" };
+                    let marker = if i + 1 < lines.len() { "+" } else { "" };
+                    let entry = format!("{} | {line}{marker}", pad_right(name, name_width));
+                    text.push_str(&format!("{}\n", entry.trim_end()));
+                }
+            }
+        }
+
+        text
+    }
+}
+
+/// Query results laid out as a [`Grid`] while they arrive in batches.
+///
+/// The layout is settled by the first batch: its values set the column
+/// widths, and in `auto` mode whether records are expanded. Later values that
+/// are wider are not cut, so they push the rest of their line out of
+/// alignment.
+pub struct GridStream {
+    mode: ExpandedMode,
+    layout: Option<Layout>,
+    /// Records laid out so far
+    count: usize,
+}
+
+enum Layout {
+    Aligned(Vec<usize>),
+    Expanded {
+        name_width: usize,
+        value_width: usize,
+    },
+}
+
+impl GridStream {
+    pub fn new(mode: ExpandedMode) -> Self {
+        GridStream {
+            mode,
+            layout: None,
+            count: 0,
+        }
+    }
+
+    /// Lays out the next batch, after the header when it is the first.
+    pub fn push(&mut self, records: &Records) -> String {
+        let grid = Grid::from_records(records);
+        let mut text = String::new();
+
+        let layout = self.layout.get_or_insert_with(|| {
+            let expanded = match self.mode {
+                ExpandedMode::Off => false,
+                ExpandedMode::On => true,
+                ExpandedMode::Auto => {
+                    terminal_width().is_some_and(|width| grid.aligned_width() > width)
+                }
+            };
+            if expanded {
+                Layout::Expanded {
+                    name_width: grid.name_width(),
+                    value_width: grid.value_width(),
+                }
+            } else {
+                let widths = grid.column_widths();
+                text.push_str(&grid.aligned_header(&widths));
+                Layout::Aligned(widths)
+            }
+        });
+
+        match layout {
+            Layout::Aligned(widths) => text.push_str(&grid.aligned_rows(widths)),
+            Layout::Expanded {
+                name_width,
+                value_width,
+            } => text.push_str(&grid.expanded_records(self.count + 1, *name_width, *value_width)),
+        }
+        self.count += grid.rows.len();
+
+        text
+    }
+
+    /// The row count closing the aligned layout.
+    pub fn footer(&self) -> String {
+        match self.layout {
+            Some(Layout::Aligned(_)) => format!("{}\n", row_count(self.count).dimmed()),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Width of the terminal stdout is attached to, or `None` when stdout is not
+/// a terminal.
+pub fn terminal_width() -> Option<usize> {
+    Term::stdout()
+        .size_checked()
+        .map(|(_, columns)| usize::from(columns))
+}
+
+fn cell_lines(value: &str) -> Vec<&str> {
+    let lines: Vec<&str> = value.lines().collect();
+    if lines.is_empty() {
+        vec![""]
+    } else {
+        lines
+    }
+}
+
+fn row_count(rows: usize) -> String {
+    if rows == 1 {
+        "(1 row)".to_string()
+    } else {
+        format!("({rows} rows)")
+    }
+}
+
+fn pad_right(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(measure_text_width(text));
+    format!("{text}{}", " ".repeat(fill))
+}
+
+fn pad_left(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(measure_text_width(text));
+    format!("{}{text}", " ".repeat(fill))
+}
+
+fn center(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(measure_text_width(text));
+    let left = fill / 2;
+    format!("{}{text}{}", " ".repeat(left), " ".repeat(fill - left))
+}
@@ -0,0 +1,261 @@
+use crate::cli::variables::is_valid_name;
+use crate::cli::ExpandedMode;
+use crate::error::DconError;
+use crate::models::catalog::NamePattern;
+use crate::models::table::QualifiedName;
+use std::path::PathBuf;
+
+/// A psql-style backslash command entered in interactive mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaCommand {
+    /// `\q`
+    Quit,
+    /// `\?` or `\h`
+    Help,
+    /// `\r`: discard the query buffer
+    ResetBuffer,
+    /// `\l`
+    ListDatabases,
+    /// `\d` without arguments
+    ListTables,
+    /// `\d name`
+    Describe(QualifiedName),
+    /// `\dt`, `\dv`, `\ds`, and `\d` with a wildcard pattern, listing the
+    /// given `pg_class.relkind`s
+    ListRelations {
+        kinds: &'static str,
+        pattern: Option<NamePattern>,
+    },
+    /// `\di`
+    ListIndexes(Option<NamePattern>),
+    /// `\df`
+    ListFunctions(Option<NamePattern>),
+    /// `\dn`
+    ListSchemas(Option<NamePattern>),
+    /// `\du`
+    ListRoles(Option<NamePattern>),
+    /// `\dx`
+    ListExtensions(Option<NamePattern>),
+    /// `\c [database [user]]`: reconnect, keeping the rest of the settings
+    Connect {
+        database: Option<String>,
+        user: Option<String>,
+    },
+    /// `\conninfo`
+    ConnInfo,
+    /// `\x [on|off|auto]` or `\pset expanded`: toggle or set expanded output
+    Expanded(Option<ExpandedMode>),
+    /// `\pset pager [on|off]`: toggle or set paging of long output
+    Pager(Option<bool>),
+    /// `\timing [on|off]`: toggle or set statement timing
+    Timing(Option<bool>),
+    /// `\set [name [value ...]]`: set a variable, or list them all
+    Set(Option<(String, String)>),
+    /// `\unset name`
+    Unset(String),
+    /// `\i file`: run commands from a file
+    Include(PathBuf),
+    /// `\o [file]`: send results to a file, or back to stdout
+    Output(Option<PathBuf>),
+    /// `\e`: edit the query buffer in an external editor
+    Edit,
+}
+
+impl MetaCommand {
+    /// Parses a line starting with a backslash.
+    pub fn parse(line: &str) -> Result<Self, DconError> {
+        let line = line.trim();
+        let (command, rest) = line
+            .find(char::is_whitespace)
+            .map_or((line, ""), |i| (&line[..i], &line[i..]));
+        let mut args = arguments(rest)?.into_iter();
+        let mut pattern = || args.next().map(|arg| parse_pattern(&arg)).transpose();
+
+        let command = match command {
+            "\\q" => MetaCommand::Quit,
+            "\\?" | "\\h" => MetaCommand::Help,
+            "\\r" => MetaCommand::ResetBuffer,
+            "\\l" => MetaCommand::ListDatabases,
+            "\\d" => match args.next() {
+                None => MetaCommand::ListTables,
+                Some(arg) if arg.contains(['*', '?']) => MetaCommand::ListRelations {
+                    kinds: "rpvmSf",
+                    pattern: Some(parse_pattern(&arg)?),
+                },
+                Some(arg) => MetaCommand::Describe(arg.parse().map_err(DconError::UserInput)?),
+            },
+            "\\dt" => MetaCommand::ListRelations {
+                kinds: "rp",
+                pattern: pattern()?,
+            },
+            "\\dv" => MetaCommand::ListRelations {
+                kinds: "v",
+                pattern: pattern()?,
+            },
+            "\\ds" => MetaCommand::ListRelations {
+                kinds: "S",
+                pattern: pattern()?,
+            },
+            "\\di" => MetaCommand::ListIndexes(pattern()?),
+            "\\df" => MetaCommand::ListFunctions(pattern()?),
+            "\\dn" => MetaCommand::ListSchemas(pattern()?),
+            "\\du" => MetaCommand::ListRoles(pattern()?),
+            "\\dx" => MetaCommand::ListExtensions(pattern()?),
+            "\\c" | "\\connect" => MetaCommand::Connect {
+                // psql uses "-" for "keep the current value".
+                database: args.next().filter(|arg| arg != "-").map(unquote),
+                user: args.next().filter(|arg| arg != "-").map(unquote),
+            },
+            "\\conninfo" => MetaCommand::ConnInfo,
+            "\\x" => MetaCommand::Expanded(expanded_mode(command, args.next())?),
+            "\\pset" => match args.next().as_deref() {
+                Some("expanded" | "x") => {
+                    MetaCommand::Expanded(expanded_mode(command, args.next())?)
+                }
+                Some("pager") => MetaCommand::Pager(switch(command, args.next())?),
+                Some(option) => {
+                    return Err(DconError::UserInput(format!(
+                        "\\pset: unknown option: {option} (expected expanded or pager)"
+                    )))
+                }
+                None => {
+                    return Err(DconError::UserInput(
+                        "\\pset: missing required argument".to_string(),
+                    ))
+                }
+            },
+            "\\timing" => MetaCommand::Timing(switch(command, args.next())?),
+            "\\set" => match args.next() {
+                None => MetaCommand::Set(None),
+                Some(name) => {
+                    variable_name(command, &name)?;
+                    // As in psql, further arguments are joined into the value.
+                    let value: String = args.by_ref().collect();
+                    MetaCommand::Set(Some((name, value)))
+                }
+            },
+            "\\unset" => match args.next() {
+                Some(name) => {
+                    variable_name(command, &name)?;
+                    MetaCommand::Unset(name)
+                }
+                None => {
+                    return Err(DconError::UserInput(
+                        "\\unset: missing required argument".to_string(),
+                    ))
+                }
+            },
+            "\\i" => match args.next() {
+                Some(file) => MetaCommand::Include(PathBuf::from(unquote(file))),
+                None => {
+                    return Err(DconError::UserInput(
+                        "\\i: missing required argument".to_string(),
+                    ))
+                }
+            },
+            "\\o" => MetaCommand::Output(args.next().map(|file| PathBuf::from(unquote(file)))),
+            "\\e" => MetaCommand::Edit,
+            _ => {
+                return Err(DconError::UserInput(format!(
+                    "Invalid command {command}. Try \\? for help."
+                )))
+            }
+        };
+
+        if let Some(extra) = args.next() {
+            return Err(DconError::UserInput(format!(
+                "{}: unexpected argument \"{extra}\"",
+                line.split_whitespace().next().unwrap_or_default()
+            )));
+        }
+
+        Ok(command)
+    }
+}
+
+/// Splits the arguments of a backslash command at whitespace. Double-quoted
+/// parts keep their quotes, since they matter for identifiers; single-quoted
+/// parts lose them, so `'my file.sql'` is one argument.
+fn arguments(input: &str) -> Result<Vec<String>, DconError> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+
+    for c in input.chars() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, '\'') => {
+                quote = Some('\'');
+                current.get_or_insert_with(String::new);
+            }
+            (None, '"') => {
+                quote = Some('"');
+                current.get_or_insert_with(String::new).push(c);
+            }
+            (Some('\''), '\'') => quote = None,
+            (Some('"'), '"') => {
+                quote = None;
+                current.get_or_insert_with(String::new).push(c);
+            }
+            (_, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(DconError::UserInput(
+            "Unterminated quoted string in command arguments".to_string(),
+        ));
+    }
+    args.extend(current);
+
+    Ok(args)
+}
+
+/// Removes the double quotes around a file or database name.
+fn unquote(arg: String) -> String {
+    match arg.strip_prefix('"').and_then(|arg| arg.strip_suffix('"')) {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => arg,
+    }
+}
+
+fn variable_name(command: &str, name: &str) -> Result<(), DconError> {
+    if is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(DconError::UserInput(format!(
+            "{command}: invalid variable name: \"{name}\""
+        )))
+    }
+}
+
+fn parse_pattern(arg: &str) -> Result<NamePattern, DconError> {
+    arg.parse().map_err(DconError::UserInput)
+}
+
+/// The value of an `on`/`off`/`auto` argument for expanded output, or `None`
+/// to toggle.
+fn expanded_mode(command: &str, arg: Option<String>) -> Result<Option<ExpandedMode>, DconError> {
+    match arg {
+        Some(arg) if arg.eq_ignore_ascii_case("auto") => Ok(Some(ExpandedMode::Auto)),
+        arg => Ok(switch(command, arg)?.map(|on| {
+            if on {
+                ExpandedMode::On
+            } else {
+                ExpandedMode::Off
+            }
+        })),
+    }
+}
+
+/// The value of an `on`/`off` argument, or `None` to toggle.
+fn switch(command: &str, arg: Option<String>) -> Result<Option<bool>, DconError> {
+    match arg.as_deref().map(str::to_lowercase).as_deref() {
+        None => Ok(None),
+        Some("on" | "true" | "yes" | "1") => Ok(Some(true)),
+        Some("off" | "false" | "no" | "0") => Ok(Some(false)),
+        Some(other) => Err(DconError::UserInput(format!(
+            "{command}: unrecognized value \"{other}\": Boolean expected"
+        ))),
+    }
+}
@@ -1,4 +1,13 @@
 pub mod commands;
+pub mod completion;
+pub mod csv;
+pub mod grid;
+pub mod meta;
+pub mod output;
+pub mod pager;
+pub mod repl;
+pub mod splitter;
 pub mod types;
+pub mod variables;
 
 pub use types::*;
@@ -0,0 +1,609 @@
+use crate::cli::csv::{CsvOptions, CsvWriter};
+use crate::cli::grid::{Grid, GridStream};
+use crate::cli::{DisplayOptions, ExpandedMode, OutputFormat};
+use crate::db::value::{result_value, PgValue};
+use crate::error::DconError;
+use crate::models::table::display_ident;
+use colored::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tabled::builder::Builder;
+use tabled::Tabled;
+use tokio_postgres::types::Type;
+use tokio_postgres::{Column, Row};
+
+/// A list of records with the same columns, from a query or from one of the
+/// model types, ready to be written in any output format.
+pub struct Records {
+    /// Heading shown above the records when a command prints several lists
+    pub title: Option<String>,
+    /// Table the `sql-insert` statements insert into, as written in SQL
+    pub table: String,
+    /// Column headers, as shown in tables
+    pub headers: Vec<String>,
+    /// Column names used as keys and in `INSERT` statements
+    pub keys: Vec<String>,
+    /// Each record's values as text, `None` for NULL
+    pub rows: Vec<Vec<Option<String>>>,
+    /// Each record as a JSON object
+    pub values: Vec<Value>,
+    /// Columns holding numbers, right-aligned and written without quotes
+    pub numeric: Vec<bool>,
+    /// Query results, drawn as a psql-style grid instead of a bordered table
+    pub query: bool,
+}
+
+impl Records {
+    /// Query results with the given columns, which are known from the
+    /// prepared statement even when no rows were returned. `table` is written
+    /// in SQL, e.g. `sales.orders`.
+    pub fn from_columns(table: &str, columns: &[Column], rows: &[Row]) -> Self {
+        let names: Vec<String> = columns.iter().map(|col| col.name().to_string()).collect();
+
+        let mut records = Records {
+            title: None,
+            table: table.to_string(),
+            headers: names.clone(),
+            keys: names,
+            rows: Vec::with_capacity(rows.len()),
+            values: Vec::with_capacity(rows.len()),
+            numeric: columns.iter().map(|col| is_numeric(col.type_())).collect(),
+            query: true,
+        };
+
+        for row in rows {
+            let mut object = Map::new();
+            let mut texts = Vec::with_capacity(columns.len());
+            for (i, column) in columns.iter().enumerate() {
+                let value = result_value(row, i, column.type_());
+                object.insert(column.name().to_string(), value.to_json());
+                texts.push(match value {
+                    PgValue::Null => None,
+                    value => Some(value.to_string()),
+                });
+            }
+            records.rows.push(texts);
+            records.values.push(Value::Object(object));
+        }
+
+        records
+    }
+
+    /// Model values, keyed by their serialized field names.
+    pub fn from_items<T: Tabled + Serialize>(table: &str, items: &[T]) -> Result<Self, DconError> {
+        let values = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<Value>, _>>()?;
+        let keys = match values.first() {
+            Some(Value::Object(object)) => object.keys().cloned().collect(),
+            _ => T::headers()
+                .iter()
+                .map(|header| header.to_string())
+                .collect(),
+        };
+
+        Ok(Records {
+            title: None,
+            table: table.to_string(),
+            headers: T::headers().into_iter().map(String::from).collect(),
+            keys,
+            rows: items
+                .iter()
+                .map(|item| item.fields().into_iter().map(|f| Some(f.into())).collect())
+                .collect(),
+            values,
+            numeric: vec![false; T::LENGTH],
+            query: false,
+        })
+    }
+
+    /// Sets the heading shown above the records.
+    pub fn titled(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Writes results in one output format. Every command builds `Records` and
+/// leaves the layout to the formatter for the selected `--format`.
+pub trait Formatter {
+    /// Writes query results a batch of records at a time.
+    fn row_writer(&self) -> Box<dyn RowWriter>;
+
+    /// Formats one list of records.
+    fn records(&self, records: &Records) -> Result<String, DconError> {
+        let mut writer = self.row_writer();
+        let mut text = writer.rows(records)?;
+        text.push_str(&writer.finish()?);
+        Ok(text)
+    }
+
+    /// Formats a document made of several lists, such as a table description.
+    /// `document` is the whole of it, for formats that serialize it as is;
+    /// the others show each non-empty section in turn.
+    fn document(&self, _document: &Value, sections: &[Records]) -> Result<String, DconError> {
+        let parts = sections
+            .iter()
+            .filter(|section| !section.is_empty())
+            .map(|section| self.records(section))
+            .collect::<Result<Vec<String>, DconError>>()?;
+        Ok(parts.join("\n"))
+    }
+}
+
+/// Writes a result in batches as its rows arrive, so that large results can
+/// be printed without holding them in memory. The batches together come out
+/// the same as the whole result given to [`Formatter::records`], except in
+/// table format, whose column widths are taken from the first batch.
+pub trait RowWriter {
+    /// Formats the next batch of records; the first also brings any header.
+    fn rows(&mut self, records: &Records) -> Result<String, DconError>;
+
+    /// Text closing the result, after the last batch.
+    fn finish(&mut self) -> Result<String, DconError> {
+        Ok(String::new())
+    }
+}
+
+/// The formatter for `format`, set up from the display options.
+pub fn formatter(format: &OutputFormat, display: &DisplayOptions) -> Box<dyn Formatter> {
+    match format {
+        OutputFormat::Table => Box::new(TableFormatter {
+            expanded: display.expanded,
+        }),
+        OutputFormat::Json => Box::new(JsonFormatter),
+        OutputFormat::Ndjson => Box::new(NdjsonFormatter),
+        OutputFormat::Yaml => Box::new(YamlFormatter),
+        OutputFormat::Csv => Box::new(CsvFormatter {
+            options: display.csv.clone(),
+        }),
+        OutputFormat::Tsv => Box::new(TsvFormatter),
+        OutputFormat::Markdown => Box::new(MarkdownFormatter),
+        OutputFormat::Html => Box::new(HtmlFormatter),
+        OutputFormat::SqlInsert => Box::new(SqlInsertFormatter),
+    }
+}
+
+/// Bordered tables for model values; query results as psql's aligned grid.
+struct TableFormatter {
+    expanded: ExpandedMode,
+}
+
+impl Formatter for TableFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(GridStream::new(self.expanded))
+    }
+
+    fn records(&self, records: &Records) -> Result<String, DconError> {
+        let body = if records.query {
+            Grid::from_records(records).render(self.expanded)
+        } else {
+            let mut builder = Builder::default();
+            builder.set_header(records.headers.clone());
+            for row in &records.rows {
+                builder.push_record(row.iter().map(|value| value.clone().unwrap_or_default()));
+            }
+            format!("{}\n", builder.build())
+        };
+
+        Ok(match &records.title {
+            Some(title) => format!("{}\n{body}", title.bright_blue().bold()),
+            None => body,
+        })
+    }
+}
+
+impl RowWriter for GridStream {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        Ok(self.push(records))
+    }
+
+    fn finish(&mut self) -> Result<String, DconError> {
+        Ok(self.footer())
+    }
+}
+
+/// A pretty-printed JSON array of objects.
+struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(JsonRows { count: 0 })
+    }
+
+    fn document(&self, document: &Value, _sections: &[Records]) -> Result<String, DconError> {
+        Ok(format!("{}\n", serde_json::to_string_pretty(document)?))
+    }
+}
+
+/// Writes the array one element at a time, laid out as
+/// `serde_json::to_string_pretty` lays out the whole of it.
+struct JsonRows {
+    count: usize,
+}
+
+impl RowWriter for JsonRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let mut text = String::new();
+        for value in &records.values {
+            text.push_str(if self.count == 0 { "[\n" } else { ",\n" });
+            let element = serde_json::to_string_pretty(value)?;
+            let lines: Vec<String> = element.lines().map(|line| format!("  {line}")).collect();
+            text.push_str(&lines.join("\n"));
+            self.count += 1;
+        }
+        Ok(text)
+    }
+
+    fn finish(&mut self) -> Result<String, DconError> {
+        Ok(if self.count == 0 { "[]\n" } else { "\n]\n" }.to_string())
+    }
+}
+
+/// One compact JSON object per line, for `jq` and log tooling.
+struct NdjsonFormatter;
+
+impl Formatter for NdjsonFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(NdjsonFormatter)
+    }
+
+    fn document(&self, document: &Value, _sections: &[Records]) -> Result<String, DconError> {
+        Ok(format!("{}\n", serde_json::to_string(document)?))
+    }
+}
+
+impl RowWriter for NdjsonFormatter {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let mut text = String::new();
+        for value in &records.values {
+            text.push_str(&serde_json::to_string(value)?);
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+/// A YAML sequence of mappings.
+struct YamlFormatter;
+
+impl Formatter for YamlFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(YamlRows { count: 0 })
+    }
+
+    fn document(&self, document: &Value, _sections: &[Records]) -> Result<String, DconError> {
+        serde_yaml::to_string(document).map_err(yaml_error)
+    }
+}
+
+/// Writes the sequence a few elements at a time; the parts of a block
+/// sequence simply follow one another.
+struct YamlRows {
+    count: usize,
+}
+
+impl RowWriter for YamlRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        if records.values.is_empty() {
+            return Ok(String::new());
+        }
+        self.count += records.values.len();
+        serde_yaml::to_string(&records.values).map_err(yaml_error)
+    }
+
+    fn finish(&mut self) -> Result<String, DconError> {
+        Ok(if self.count == 0 { "[]\n" } else { "" }.to_string())
+    }
+}
+
+/// Comma-separated values in the dialect set by the `--csv-*` options;
+/// sections are separated by a blank line.
+struct CsvFormatter {
+    options: CsvOptions,
+}
+
+impl Formatter for CsvFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(CsvRows {
+            options: self.options.clone(),
+            started: false,
+        })
+    }
+
+    fn document(&self, _document: &Value, sections: &[Records]) -> Result<String, DconError> {
+        let parts = sections
+            .iter()
+            .filter(|section| !section.is_empty())
+            .map(|section| self.records(section))
+            .collect::<Result<Vec<String>, DconError>>()?;
+        Ok(parts.join(self.options.line_ending.as_str()))
+    }
+}
+
+struct CsvRows {
+    options: CsvOptions,
+    started: bool,
+}
+
+impl RowWriter for CsvRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let mut writer = CsvWriter::new(&self.options);
+        if self.options.header && !self.started {
+            writer.record(records.headers.iter().map(|header| Some(header.as_str())));
+        }
+        for row in &records.rows {
+            writer.record(row.iter().map(Option::as_deref));
+        }
+        self.started = true;
+        Ok(writer.finish())
+    }
+}
+
+/// Tab-separated values. Tabs, line breaks and backslashes in values are
+/// escaped as in `COPY ... TO` text format, so each record is one line, and
+/// NULL is `\N`.
+struct TsvFormatter;
+
+impl Formatter for TsvFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(TsvRows { started: false })
+    }
+}
+
+struct TsvRows {
+    started: bool,
+}
+
+impl RowWriter for TsvRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let line = |fields: Vec<&str>| {
+            let fields: Vec<String> = fields.into_iter().map(tsv_field).collect();
+            format!("{}\n", fields.join("\t"))
+        };
+
+        let mut text = String::new();
+        if !self.started {
+            text.push_str(&line(records.headers.iter().map(String::as_str).collect()));
+            self.started = true;
+        }
+        for row in &records.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|value| {
+                    value
+                        .as_deref()
+                        .map_or_else(|| "\\N".to_string(), tsv_field)
+                })
+                .collect();
+            text.push_str(&format!("{}\n", fields.join("\t")));
+        }
+        Ok(text)
+    }
+}
+
+/// A GitHub-flavored Markdown table, under a heading when titled.
+struct MarkdownFormatter;
+
+impl Formatter for MarkdownFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(MarkdownRows { started: false })
+    }
+}
+
+struct MarkdownRows {
+    started: bool,
+}
+
+impl RowWriter for MarkdownRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let line = |fields: Vec<String>| format!("| {} |\n", fields.join(" | "));
+
+        let mut text = String::new();
+        if !self.started {
+            if let Some(title) = &records.title {
+                text.push_str(&format!("### {title}\n\n"));
+            }
+            text.push_str(&line(
+                records.headers.iter().map(|h| markdown_field(h)).collect(),
+            ));
+            text.push_str(&line(
+                records
+                    .numeric
+                    .iter()
+                    .map(|&numeric| if numeric { "---:" } else { "---" }.to_string())
+                    .collect(),
+            ));
+            self.started = true;
+        }
+        for row in &records.rows {
+            text.push_str(&line(
+                row.iter()
+                    .map(|value| markdown_field(value.as_deref().unwrap_or("")))
+                    .collect(),
+            ));
+        }
+        Ok(text)
+    }
+}
+
+/// An HTML `<table>`, under an `<h3>` when titled.
+struct HtmlFormatter;
+
+impl Formatter for HtmlFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(HtmlRows { started: false })
+    }
+}
+
+struct HtmlRows {
+    started: bool,
+}
+
+impl RowWriter for HtmlRows {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let mut text = String::new();
+        if !self.started {
+            if let Some(title) = &records.title {
+                text.push_str(&format!("<h3>{}</h3>\n", html_escape(title)));
+            }
+            text.push_str("<table>\n  <thead>\n    <tr>");
+            for header in &records.headers {
+                text.push_str(&format!("<th>{}</th>", html_escape(header)));
+            }
+            text.push_str("</tr>\n  </thead>\n  <tbody>\n");
+            self.started = true;
+        }
+        for row in &records.rows {
+            text.push_str("    <tr>");
+            for (value, &numeric) in row.iter().zip(&records.numeric) {
+                let value = html_escape(value.as_deref().unwrap_or("")).replace('\n', "<br>");
+                if numeric {
+                    text.push_str(&format!("<td align=\"right\">{value}</td>"));
+                } else {
+                    text.push_str(&format!("<td>{value}</td>"));
+                }
+            }
+            text.push_str("</tr>\n");
+        }
+
+        Ok(text)
+    }
+
+    fn finish(&mut self) -> Result<String, DconError> {
+        Ok(if self.started {
+            "  </tbody>\n</table>\n"
+        } else {
+            ""
+        }
+        .to_string())
+    }
+}
+
+/// One `INSERT` statement per record, into `Records::table`.
+struct SqlInsertFormatter;
+
+impl Formatter for SqlInsertFormatter {
+    fn row_writer(&self) -> Box<dyn RowWriter> {
+        Box::new(SqlInsertFormatter)
+    }
+}
+
+impl RowWriter for SqlInsertFormatter {
+    fn rows(&mut self, records: &Records) -> Result<String, DconError> {
+        let columns: Vec<String> = records.keys.iter().map(|key| display_ident(key)).collect();
+
+        let mut text = String::new();
+        for row in &records.rows {
+            let values: Vec<String> = row
+                .iter()
+                .zip(&records.numeric)
+                .map(|(value, &numeric)| sql_literal(value.as_deref(), numeric))
+                .collect();
+            text.push_str(&format!(
+                "INSERT INTO {} ({}) VALUES ({});\n",
+                records.table,
+                columns.join(", "),
+                values.join(", ")
+            ));
+        }
+        Ok(text)
+    }
+}
+
+fn is_numeric(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::NUMERIC
+            | Type::OID
+            | Type::MONEY
+    )
+}
+
+fn yaml_error(e: serde_yaml::Error) -> DconError {
+    DconError::UserInput(format!("Failed to write YAML: {e}"))
+}
+
+fn tsv_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn markdown_field(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// A value as an SQL literal. Numbers are written bare, except special
+/// values such as `NaN` that PostgreSQL only accepts as strings.
+fn sql_literal(value: Option<&str>, numeric: bool) -> String {
+    match value {
+        None => "NULL".to_string(),
+        Some(value) if numeric && value.parse::<f64>().is_ok_and(f64::is_finite) => {
+            value.to_string()
+        }
+        Some(value) => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(rows: Vec<Vec<Option<&str>>>) -> Records {
+        Records {
+            title: None,
+            table: "t".to_string(),
+            headers: vec!["a".to_string(), "b".to_string()],
+            keys: vec!["a".to_string(), "b".to_string()],
+            rows: rows
+                .into_iter()
+                .map(|row| row.into_iter().map(|v| v.map(str::to_string)).collect())
+                .collect(),
+            values: Vec::new(),
+            numeric: vec![false, false],
+            query: true,
+        }
+    }
+
+    #[test]
+    fn tsv_escapes_like_copy() {
+        let records = records(vec![
+            vec![None, Some("")],
+            vec![Some("tab\there"), Some("two\nlines\r")],
+            vec![Some("back\\slash"), Some("\\N")],
+        ]);
+        let text = formatter(&OutputFormat::Tsv, &DisplayOptions::default())
+            .records(&records)
+            .expect("formatted");
+        assert_eq!(
+            text,
+            "a\tb\n\\N\t\ntab\\there\ttwo\\nlines\\r\nback\\\\slash\t\\\\N\n"
+        );
+    }
+}
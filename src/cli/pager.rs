@@ -0,0 +1,104 @@
+use crate::error::DconError;
+use crate::interrupt::Hold;
+use console::{measure_text_width, Term};
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when neither `DCON_PAGER` nor `PAGER` is set: `-S` chops long
+/// lines instead of wrapping them, `-R` passes colors through and `-F` quits
+/// straight away if the text fits on one screen after all.
+const DEFAULT_PAGER: &str = "less -SRF";
+
+/// Prints `text`, through the pager when `enabled`, stdout is a terminal and
+/// the text is taller or wider than the terminal.
+///
+/// The pager is `$DCON_PAGER`, then `$PAGER`, then `less -SRF`; setting
+/// either variable to an empty string turns paging off. If the pager cannot
+/// be started the text is printed directly.
+pub fn page(text: &str, enabled: bool) -> Result<(), DconError> {
+    let command = match pager_command() {
+        Some(command) if enabled && !fits_terminal(text) => command,
+        _ => return print(text),
+    };
+
+    // Once the pager runs it has shown some of the text, so failures past
+    // that point do not print it again.
+    match run_pager(&command, text) {
+        Err(PagerError::Spawn) => print(text),
+        Err(PagerError::Run(e)) => Err(DconError::io(e, "Pager failed")),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Writes `text` to stdout straight away. A reader that has gone away, as
+/// `head` does once it has read enough, ends the output without an error.
+pub fn print(text: &str) -> Result<(), DconError> {
+    let mut stdout = io::stdout().lock();
+    match stdout
+        .write_all(text.as_bytes())
+        .and_then(|()| stdout.flush())
+    {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            Err(DconError::io(e, "Failed to write output"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn pager_command() -> Option<String> {
+    match env::var("DCON_PAGER").or_else(|_| env::var("PAGER")) {
+        Ok(command) if command.trim().is_empty() => None,
+        Ok(command) => Some(command),
+        Err(_) => Some(DEFAULT_PAGER.to_string()),
+    }
+}
+
+/// Whether the text fits on the screen, which it always does when stdout is
+/// not a terminal.
+fn fits_terminal(text: &str) -> bool {
+    let Some((rows, columns)) = Term::stdout().size_checked() else {
+        return true;
+    };
+
+    // Leave a line for the prompt that follows the output.
+    text.lines().count() < usize::from(rows)
+        && text
+            .lines()
+            .all(|line| measure_text_width(line) <= usize::from(columns))
+}
+
+enum PagerError {
+    /// The pager could not be started, so nothing was shown
+    Spawn,
+    /// The pager failed after starting
+    Run(io::Error),
+}
+
+fn run_pager(command: &str, text: &str) -> Result<(), PagerError> {
+    // The variable may carry arguments, as the default does.
+    let mut words = command.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    io::stdout().flush().map_err(|_| PagerError::Spawn)?;
+    // The pager gets Ctrl-C too, and deals with it.
+    let _hold = Hold::start();
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|_| PagerError::Spawn)?;
+
+    let written = match child.stdin.take() {
+        Some(mut stdin) => match stdin.write_all(text.as_bytes()) {
+            // The pager was quit before reading everything.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result,
+        },
+        None => Ok(()),
+    };
+    // Closing stdin, done by now, lets the pager finish; wait for it even if
+    // writing failed.
+    let waited = child.wait();
+    written.and(waited.map(|_| ())).map_err(PagerError::Run)
+}
@@ -0,0 +1,204 @@
+use crate::cli::completion::SqlHelper;
+use crate::cli::EditMode;
+use crate::config::data_dir;
+use crate::error::DconError;
+use crate::models::catalog::CatalogNames;
+use regex::Regex;
+use rustyline::config::{CompletionType, Config};
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use rustyline::Editor;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Number of entries kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+
+/// Settings for the interactive line editor.
+#[derive(Debug, Clone)]
+pub struct ReplOptions {
+    pub edit_mode: EditMode,
+    /// History file, or `None` to keep history for this session only
+    pub history_path: Option<PathBuf>,
+    /// Keep statements that contain passwords out of the history
+    pub hide_secrets: bool,
+}
+
+/// One read from the line editor.
+pub enum ReadLine {
+    Line(String),
+    /// Ctrl-C: the current input was discarded
+    Interrupted,
+    /// Ctrl-D on an empty line
+    Eof,
+}
+
+/// Line editor with emacs or vi key bindings, Ctrl-R history search, tab
+/// completion and a history file that persists between sessions.
+pub struct LineEditor {
+    editor: Editor<SqlHelper, FileHistory>,
+    history_path: Option<PathBuf>,
+    hide_secrets: bool,
+}
+
+impl LineEditor {
+    pub fn new(options: &ReplOptions) -> Result<Self, DconError> {
+        let edit_mode = match options.edit_mode {
+            EditMode::Emacs => rustyline::EditMode::Emacs,
+            EditMode::Vi => rustyline::EditMode::Vi,
+        };
+        let config = Config::builder()
+            .edit_mode(edit_mode)
+            .max_history_size(HISTORY_SIZE)
+            .map_err(readline_error)?
+            .history_ignore_dups(true)
+            .map_err(readline_error)?
+            // Lines starting with a space are not recorded, as with HISTCONTROL=ignorespace.
+            .history_ignore_space(true)
+            .auto_add_history(false)
+            // Like psql: complete the common prefix, list the candidates on a second Tab.
+            .completion_type(CompletionType::List)
+            .build();
+
+        let mut editor = Editor::with_config(config).map_err(readline_error)?;
+        editor.set_helper(Some(SqlHelper::default()));
+
+        if let Some(path) = &options.history_path {
+            if path.exists() {
+                editor.load_history(path).map_err(|e| {
+                    DconError::io(
+                        io::Error::other(e),
+                        format!("Failed to read history file '{}'", path.display()),
+                    )
+                })?;
+            }
+        }
+
+        Ok(LineEditor {
+            editor,
+            history_path: options.history_path.clone(),
+            hide_secrets: options.hide_secrets,
+        })
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> Result<ReadLine, DconError> {
+        match self.editor.readline(prompt) {
+            Ok(line) => Ok(ReadLine::Line(line)),
+            Err(ReadlineError::Interrupted) => Ok(ReadLine::Interrupted),
+            Err(ReadlineError::Eof) => Ok(ReadLine::Eof),
+            Err(e) => Err(readline_error(e)),
+        }
+    }
+
+    /// Replaces the names offered by tab completion.
+    pub fn set_catalog(&mut self, catalog: CatalogNames) {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.set_catalog(catalog);
+        }
+    }
+
+    /// Sets the earlier lines of the statement being entered, which give
+    /// completion its context.
+    pub fn set_buffer(&mut self, buffer: &str) {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.set_buffer(buffer);
+        }
+    }
+
+    /// Records an entry in the history and appends it to the history file.
+    pub fn add_history(&mut self, entry: &str) -> Result<(), DconError> {
+        if entry.trim().is_empty() || (self.hide_secrets && contains_secret(entry)) {
+            return Ok(());
+        }
+
+        if !self
+            .editor
+            .add_history_entry(entry)
+            .map_err(readline_error)?
+        {
+            return Ok(());
+        }
+
+        if let Some(path) = &self.history_path {
+            append_history(&mut self.editor, path)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn append_history(
+    editor: &mut Editor<SqlHelper, FileHistory>,
+    path: &Path,
+) -> Result<(), DconError> {
+    let context = || format!("Failed to write history file '{}'", path.display());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| DconError::io(e, context()))?;
+    }
+
+    editor
+        .append_history(path)
+        .map_err(|e| DconError::io(io::Error::other(e), context()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| DconError::io(e, context()))?;
+    }
+
+    Ok(())
+}
+
+fn readline_error(e: ReadlineError) -> DconError {
+    match e {
+        ReadlineError::Io(e) => DconError::io(e, "Line editor failed"),
+        e => DconError::io(io::Error::other(e), "Line editor failed"),
+    }
+}
+
+/// History file for a connection: one file per profile (or server, when no
+/// profile is in use) and database, under `history` in the data directory.
+pub fn history_path(connection: &str, database: &str) -> Result<PathBuf, DconError> {
+    Ok(data_dir()?
+        .join("history")
+        .join(file_name(connection))
+        .join(file_name(database)))
+}
+
+/// Makes a name safe to use as a single path component.
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    match name.trim_start_matches('.') {
+        "" => "_".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Whether the input looks like it carries a password: role definitions
+/// with a `PASSWORD` clause and connection strings with a `password=` key.
+pub fn contains_secret(input: &str) -> bool {
+    static PATTERNS: OnceLock<[Regex; 2]> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            Regex::new(r"(?is)\b(create|alter)\s+(role|user|group)\b.*\bpassword\b")
+                .expect("valid regex"),
+            Regex::new(r"(?i)\bpassword\s*=").expect("valid regex"),
+        ]
+    });
+
+    patterns.iter().any(|pattern| pattern.is_match(input))
+}
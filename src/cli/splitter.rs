@@ -0,0 +1,626 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A complete SQL statement taken from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    /// Statement text without the terminating semicolon
+    pub text: String,
+    /// 1-based input line on which the statement starts
+    pub line: usize,
+}
+
+/// What an unfinished statement is waiting for, used for the continuation
+/// prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pending {
+    /// Nothing is buffered
+    None,
+    /// A statement without its terminating semicolon
+    Statement,
+    SingleQuote,
+    DoubleQuote,
+    DollarQuote,
+    BlockComment,
+    Parenthesis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Normal,
+    SingleQuote { backslash_escapes: bool },
+    DoubleQuote,
+    DollarQuote(String),
+    LineComment,
+    BlockComment(usize),
+}
+
+/// Splits SQL text into statements at top-level semicolons.
+///
+/// Semicolons inside string literals, quoted identifiers, dollar-quoted
+/// bodies, comments, parentheses and `BEGIN ATOMIC ... END` function bodies
+/// do not end a statement. Input can be fed incrementally, one line at a
+/// time, and statements are returned as soon as they are complete.
+#[derive(Debug)]
+pub struct StatementSplitter {
+    buffer: String,
+    state: State,
+    /// Line of the next input character
+    line: usize,
+    /// Line on which the buffered statement starts
+    start_line: Option<usize>,
+    parens: usize,
+    /// Nesting of `BEGIN ATOMIC` blocks (and `CASE` inside them)
+    atomic_depth: usize,
+    /// Words of the buffered statement, upper-cased, as far as they matter
+    /// for recognising `CREATE ... BEGIN ATOMIC`
+    first_word: Option<String>,
+    previous_word: Option<String>,
+    word: String,
+}
+
+impl Default for StatementSplitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatementSplitter {
+    pub fn new() -> Self {
+        StatementSplitter {
+            buffer: String::new(),
+            state: State::Normal,
+            line: 1,
+            start_line: None,
+            parens: 0,
+            atomic_depth: 0,
+            first_word: None,
+            previous_word: None,
+            word: String::new(),
+        }
+    }
+
+    /// Splits a complete script. Trailing text without a semicolon counts as
+    /// a final statement.
+    pub fn split(sql: &str) -> Vec<Statement> {
+        let mut splitter = StatementSplitter::new();
+        let mut statements = splitter.push(sql);
+        statements.extend(splitter.finish());
+        statements
+    }
+
+    /// Feeds more input and returns the statements it completes.
+    pub fn push(&mut self, input: &str) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let next = chars.peek().copied();
+
+            match &mut self.state {
+                State::Normal => {
+                    if c.is_alphanumeric() || c == '_' {
+                        self.word.push(c);
+                    } else {
+                        self.end_word();
+                    }
+
+                    match c {
+                        '\'' => {
+                            // E'...' strings treat backslash as an escape character.
+                            let backslash_escapes = self.buffer.ends_with(['E', 'e'])
+                                && self.previous_word.as_deref() == Some("E");
+                            self.state = State::SingleQuote { backslash_escapes };
+                        }
+                        '"' => self.state = State::DoubleQuote,
+                        '-' if next == Some('-') => {
+                            self.state = State::LineComment;
+                        }
+                        '/' if next == Some('*') => {
+                            self.buffer.push(c);
+                            self.buffer.push(chars.next().unwrap_or('*'));
+                            self.state = State::BlockComment(1);
+                            continue;
+                        }
+                        '$' => {
+                            if let Some(tag) = self.dollar_tag(c, &mut chars) {
+                                self.mark_start();
+                                self.buffer.push_str(&tag);
+                                self.state = State::DollarQuote(tag);
+                                continue;
+                            }
+                        }
+                        '(' => self.parens += 1,
+                        ')' => self.parens = self.parens.saturating_sub(1),
+                        ';' if self.parens == 0 && self.atomic_depth == 0 => {
+                            statements.extend(self.take_statement());
+                            continue;
+                        }
+                        _ => {}
+                    }
+
+                    if !c.is_whitespace() && self.state != State::LineComment {
+                        self.mark_start();
+                    }
+                }
+                State::SingleQuote { backslash_escapes } => match c {
+                    '\\' if *backslash_escapes => {
+                        self.buffer.push(c);
+                        if let Some(escaped) = chars.next() {
+                            self.count_line(escaped);
+                            self.buffer.push(escaped);
+                        }
+                        continue;
+                    }
+                    // A doubled quote is an escaped quote, not the end of the string.
+                    '\'' if next == Some('\'') => {
+                        self.buffer.push(c);
+                        self.buffer.push(chars.next().unwrap_or('\''));
+                        continue;
+                    }
+                    '\'' => self.state = State::Normal,
+                    _ => {}
+                },
+                State::DoubleQuote => {
+                    if c == '"' {
+                        if next == Some('"') {
+                            self.buffer.push(c);
+                            self.buffer.push(chars.next().unwrap_or('"'));
+                            continue;
+                        }
+                        self.state = State::Normal;
+                    }
+                }
+                State::DollarQuote(tag) => {
+                    // The closing tag is complete once its final `$` follows the rest of it.
+                    if c == '$' && self.buffer.ends_with(&tag[..tag.len() - 1]) {
+                        self.state = State::Normal;
+                    }
+                }
+                State::LineComment => {
+                    if c == '\n' {
+                        self.state = State::Normal;
+                    }
+                }
+                State::BlockComment(depth) => match c {
+                    '*' if next == Some('/') => {
+                        *depth -= 1;
+                        if *depth == 0 {
+                            self.state = State::Normal;
+                        }
+                        self.buffer.push(c);
+                        self.buffer.push(chars.next().unwrap_or('/'));
+                        continue;
+                    }
+                    '/' if next == Some('*') => {
+                        *depth += 1;
+                        self.buffer.push(c);
+                        self.buffer.push(chars.next().unwrap_or('*'));
+                        continue;
+                    }
+                    _ => {}
+                },
+            }
+
+            self.count_line(c);
+            self.buffer.push(c);
+        }
+
+        statements
+    }
+
+    /// Returns the buffered text as a statement even though it has no
+    /// terminating semicolon, and resets the splitter.
+    pub fn finish(&mut self) -> Option<Statement> {
+        self.end_word();
+        let statement = self.take_statement();
+        self.reset();
+        statement
+    }
+
+    /// Discards buffered input.
+    pub fn reset(&mut self) {
+        let line = self.line;
+        *self = StatementSplitter::new();
+        self.line = line;
+    }
+
+    /// What the buffered input is waiting for.
+    pub fn pending(&self) -> Pending {
+        match self.state {
+            State::SingleQuote { .. } => Pending::SingleQuote,
+            State::DoubleQuote => Pending::DoubleQuote,
+            State::DollarQuote(_) => Pending::DollarQuote,
+            State::BlockComment(_) => Pending::BlockComment,
+            _ if self.parens > 0 => Pending::Parenthesis,
+            _ if self.start_line.is_some() => Pending::Statement,
+            // Nothing but whitespace and comments
+            _ => Pending::None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending() == Pending::None
+    }
+
+    fn count_line(&mut self, c: char) {
+        if c == '\n' {
+            self.line += 1;
+        }
+    }
+
+    fn mark_start(&mut self) {
+        self.start_line.get_or_insert(self.line);
+    }
+
+    /// Reads the rest of a `$tag$` opening delimiter, if `$` starts one. A
+    /// `$` that is part of an identifier or a positional parameter (`$1`)
+    /// does not.
+    fn dollar_tag(
+        &mut self,
+        c: char,
+        chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    ) -> Option<String> {
+        if self
+            .buffer
+            .ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            return None;
+        }
+
+        let lookahead: String = chars.clone().take_while(|&c| c != '$').collect();
+        let valid = chars.clone().nth(lookahead.chars().count()) == Some('$')
+            && !lookahead.starts_with(|c: char| c.is_ascii_digit())
+            && lookahead.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !valid {
+            return None;
+        }
+
+        for _ in 0..=lookahead.chars().count() {
+            chars.next();
+        }
+        Some(format!("{c}{lookahead}$"))
+    }
+
+    /// Tracks the words that open and close `BEGIN ATOMIC` bodies.
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+
+        let word = std::mem::take(&mut self.word).to_uppercase();
+        if self.first_word.is_none() {
+            self.first_word = Some(word.clone());
+        }
+
+        let in_create = self.first_word.as_deref() == Some("CREATE");
+        match word.as_str() {
+            "ATOMIC" if in_create && self.previous_word.as_deref() == Some("BEGIN") => {
+                self.atomic_depth += 1;
+            }
+            "CASE" if self.atomic_depth > 0 => self.atomic_depth += 1,
+            "END" if self.atomic_depth > 0 => self.atomic_depth -= 1,
+            _ => {}
+        }
+
+        self.previous_word = Some(word);
+    }
+
+    fn take_statement(&mut self) -> Option<Statement> {
+        self.end_word();
+        let text = std::mem::take(&mut self.buffer);
+        let line = self.start_line.take();
+
+        self.parens = 0;
+        self.atomic_depth = 0;
+        self.first_word = None;
+        self.previous_word = None;
+
+        let text = text.trim();
+        match line {
+            Some(line) if !text.is_empty() => Some(Statement {
+                text: text.to_string(),
+                line,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The command tag psql would print for a statement that returned no rows,
+/// such as `INSERT 0 3` or `CREATE TABLE`.
+///
+/// The driver only reports how many rows were affected, so the tag is
+/// rebuilt from the statement's leading keywords.
+pub fn command_tag(sql: &str, rows_affected: Option<u64>) -> String {
+    let words: Vec<String> = keywords(sql).take(6).collect();
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or_default();
+    let rows = rows_affected.unwrap_or(0);
+
+    match word(0) {
+        "INSERT" => format!("INSERT 0 {rows}"),
+        "UPDATE" | "DELETE" | "MERGE" | "SELECT" | "FETCH" | "MOVE" | "COPY" => {
+            format!("{} {rows}", word(0))
+        }
+        // The command is the one that follows the common table expressions.
+        "WITH" => {
+            let command = keywords(sql)
+                .find(|word| matches!(word.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE"))
+                .unwrap_or_else(|| "SELECT".to_string());
+            command_tag(&command, rows_affected)
+        }
+        "CREATE" | "ALTER" | "DROP" => {
+            let object = words[1..]
+                .iter()
+                .map(String::as_str)
+                .skip_while(|word| {
+                    matches!(
+                        *word,
+                        "OR" | "REPLACE"
+                            | "UNIQUE"
+                            | "TEMP"
+                            | "TEMPORARY"
+                            | "UNLOGGED"
+                            | "GLOBAL"
+                            | "LOCAL"
+                            | "TRUSTED"
+                            | "PROCEDURAL"
+                            | "RECURSIVE"
+                            | "DEFAULT"
+                    )
+                })
+                .collect::<Vec<_>>();
+            match object.as_slice() {
+                [first @ ("MATERIALIZED" | "FOREIGN" | "EVENT"), second, ..] => {
+                    format!("{} {first} {second}", word(0))
+                }
+                [first, ..] => format!("{} {first}", word(0)),
+                [] => word(0).to_string(),
+            }
+        }
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "START" => "START TRANSACTION".to_string(),
+        "END" => "COMMIT".to_string(),
+        "ABORT" => "ROLLBACK".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether a statement only reads, so that running it a second time under
+/// `EXPLAIN ANALYZE` changes nothing: a `SELECT`, `VALUES` or `TABLE` query
+/// without `INTO`, whose common table expressions do not modify data either.
+/// Functions it calls may still have side effects.
+pub fn is_read_only(sql: &str) -> bool {
+    let mut words = keywords(sql);
+    matches!(
+        words.next().as_deref(),
+        Some("SELECT" | "VALUES" | "TABLE" | "WITH")
+    ) && !words.any(|word| {
+        matches!(
+            word.as_str(),
+            "INTO" | "INSERT" | "UPDATE" | "DELETE" | "MERGE"
+        )
+    })
+}
+
+/// The words of a statement in upper case, skipping comments and quoted
+/// text.
+fn keywords(sql: &str) -> impl Iterator<Item = String> + '_ {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| {
+        Regex::new(
+            r#"--[^\n]*|/\*(?s:.*?)\*/|'(?:[^']|'')*'|"(?:[^"]|"")*"|([A-Za-z_][A-Za-z0-9_$]*)"#,
+        )
+        .expect("valid regex")
+    });
+
+    word.captures_iter(sql)
+        .filter_map(|captures| captures.get(1))
+        .map(|word| word.as_str().to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(sql: &str) -> Vec<String> {
+        StatementSplitter::split(sql)
+            .into_iter()
+            .map(|statement| statement.text)
+            .collect()
+    }
+
+    #[test]
+    fn splits_at_semicolons() {
+        assert_eq!(
+            texts("select 1; select 2;\n select 3"),
+            ["select 1", "select 2", "select 3"]
+        );
+    }
+
+    #[test]
+    fn skips_empty_statements() {
+        assert_eq!(texts(";; select 1;  ;\n"), ["select 1"]);
+        assert!(texts("  -- only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn records_start_lines() {
+        let statements = StatementSplitter::split("select 1;\n\n-- note\nselect\n2;");
+        let lines: Vec<usize> = statements.iter().map(|statement| statement.line).collect();
+        assert_eq!(lines, [1, 4]);
+    }
+
+    #[test]
+    fn single_quotes() {
+        assert_eq!(
+            texts("select 'a;b', 'it''s;'; select 2"),
+            ["select 'a;b', 'it''s;'", "select 2"]
+        );
+    }
+
+    #[test]
+    fn backslashes_escape_only_in_e_strings() {
+        assert_eq!(
+            texts(r"select E'it\'s;'; select 2"),
+            [r"select E'it\'s;'", "select 2"]
+        );
+        assert_eq!(
+            texts(r"select 'a\'; select 2"),
+            [r"select 'a\'", "select 2"]
+        );
+        // An identifier ending in E is not an E'' prefix.
+        assert_eq!(
+            texts(r"select name'a\'; select 2"),
+            [r"select name'a\'", "select 2"]
+        );
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        assert_eq!(
+            texts(r#"select 1 as "a;""b"; select 2"#),
+            [r#"select 1 as "a;""b""#, "select 2"]
+        );
+    }
+
+    #[test]
+    fn dollar_quotes() {
+        assert_eq!(
+            texts("select $$a;b$$; select $fn$ $$; $fn$; select 3"),
+            ["select $$a;b$$", "select $fn$ $$; $fn$", "select 3"]
+        );
+    }
+
+    #[test]
+    fn parameters_are_not_dollar_quotes() {
+        assert_eq!(texts("select $1; select $2"), ["select $1", "select $2"]);
+        assert_eq!(
+            texts("select $1||$$a;b$$; select 2"),
+            ["select $1||$$a;b$$", "select 2"]
+        );
+        assert_eq!(texts("select a$b; select 2"), ["select a$b", "select 2"]);
+    }
+
+    #[test]
+    fn function_body_keeps_its_semicolons() {
+        let sql = "create function f() returns int as $body$\nbegin\n  return 1;\nend;\n$body$ language plpgsql; select f()";
+        assert_eq!(
+            texts(sql),
+            [
+                "create function f() returns int as $body$\nbegin\n  return 1;\nend;\n$body$ language plpgsql",
+                "select f()"
+            ]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            texts("select 1 -- not; the end\n; select 2"),
+            ["select 1 -- not; the end", "select 2"]
+        );
+        assert_eq!(
+            texts("select /* a /* nested; */ still; */ 1; select 2"),
+            ["select /* a /* nested; */ still; */ 1", "select 2"]
+        );
+    }
+
+    #[test]
+    fn parentheses() {
+        assert_eq!(
+            texts("create rule r as on insert to t do also (insert into a values (1); insert into b values (2)); select 2"),
+            [
+                "create rule r as on insert to t do also (insert into a values (1); insert into b values (2))",
+                "select 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn begin_atomic_bodies() {
+        let body = "create function f() returns int language sql begin atomic select case when true then 1 end; select 2; end";
+        assert_eq!(texts(&format!("{body}; select 3")), [body, "select 3"]);
+    }
+
+    #[test]
+    fn transaction_blocks_are_separate_statements() {
+        assert_eq!(
+            texts("begin; select 1; end; select 2"),
+            ["begin", "select 1", "end", "select 2"]
+        );
+    }
+
+    #[test]
+    fn incremental_input() {
+        let mut splitter = StatementSplitter::new();
+        assert!(splitter.push("select 'a\n").is_empty());
+        assert_eq!(splitter.pending(), Pending::SingleQuote);
+        assert!(splitter.push("b' from (\n").is_empty());
+        assert_eq!(splitter.pending(), Pending::Parenthesis);
+        assert!(splitter.push("values (1)) v\n").is_empty());
+        assert_eq!(splitter.pending(), Pending::Statement);
+
+        let statements = splitter.push("; select $$\n");
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].text, "select 'a\nb' from (\nvalues (1)) v");
+        assert_eq!(splitter.pending(), Pending::DollarQuote);
+
+        splitter.reset();
+        assert!(splitter.is_empty());
+        splitter.push("/* open");
+        assert_eq!(splitter.pending(), Pending::BlockComment);
+    }
+
+    #[test]
+    fn command_tags() {
+        assert_eq!(
+            command_tag("insert into t values (1)", Some(3)),
+            "INSERT 0 3"
+        );
+        assert_eq!(command_tag("UPDATE t SET a = 1", Some(2)), "UPDATE 2");
+        assert_eq!(command_tag("select 1", Some(1)), "SELECT 1");
+        assert_eq!(
+            command_tag("with d as (select 1) delete from t", Some(4)),
+            "DELETE 4"
+        );
+        assert_eq!(
+            command_tag("with d as (select 1) select 1", None),
+            "SELECT 0"
+        );
+        assert_eq!(
+            command_tag("create or replace view v as select 1", None),
+            "CREATE VIEW"
+        );
+        assert_eq!(
+            command_tag("create unique index i on t (a)", None),
+            "CREATE INDEX"
+        );
+        assert_eq!(
+            command_tag("CREATE MATERIALIZED VIEW m AS SELECT 1", None),
+            "CREATE MATERIALIZED VIEW"
+        );
+        assert_eq!(command_tag("drop table t", None), "DROP TABLE");
+        assert_eq!(command_tag("truncate t", None), "TRUNCATE TABLE");
+        assert_eq!(command_tag("end", None), "COMMIT");
+        assert_eq!(
+            command_tag("-- select\n/* update */ delete from t", Some(1)),
+            "DELETE 1"
+        );
+    }
+
+    #[test]
+    fn read_only_statements() {
+        assert!(is_read_only("select * from t"));
+        assert!(is_read_only("values (1)"));
+        assert!(is_read_only("table t"));
+        assert!(is_read_only("with a as (select 1) select * from a"));
+        assert!(is_read_only("select 'insert' from t"));
+        assert!(!is_read_only("select * into t2 from t"));
+        assert!(!is_read_only(
+            "with d as (delete from t returning *) select * from d"
+        ));
+        assert!(!is_read_only("insert into t values (1)"));
+        assert!(!is_read_only("create table t (a int)"));
+    }
+}
@@ -1,10 +1,25 @@
-use clap::{Parser, Subcommand};
-
-#[derive(Clone, Parser)]
+use crate::cli::csv::CsvOptions;
+use crate::models::connection::SslMode;
+use crate::models::table::QualifiedName;
+use clap::{ArgGroup, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Parser, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Table,
     Json,
+    /// One JSON object per line
+    Ndjson,
+    Yaml,
     Csv,
+    Tsv,
+    Markdown,
+    Html,
+    /// `INSERT` statements recreating the results
+    #[serde(rename = "sql-insert")]
+    SqlInsert,
 }
 
 impl std::fmt::Display for OutputFormat {
@@ -12,7 +27,13 @@ impl std::fmt::Display for OutputFormat {
         match self {
             OutputFormat::Table => write!(f, "table"),
             OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Yaml => write!(f, "yaml"),
             OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Html => write!(f, "html"),
+            OutputFormat::SqlInsert => write!(f, "sql-insert"),
         }
     }
 }
@@ -24,12 +45,118 @@ impl std::str::FromStr for OutputFormat {
         match s.to_lowercase().as_str() {
             "table" => Ok(OutputFormat::Table),
             "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
             "csv" => Ok(OutputFormat::Csv),
-            _ => Err(format!("Invalid output format: {s}")),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            "sql-insert" | "sql" => Ok(OutputFormat::SqlInsert),
+            _ => Err(format!(
+                "Invalid output format: {s} (expected table, json, ndjson, yaml, csv, tsv, markdown, html or sql-insert)"
+            )),
+        }
+    }
+}
+
+/// Key bindings used by the interactive line editor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EditMode {
+    #[default]
+    Emacs,
+    Vi,
+}
+
+impl std::fmt::Display for EditMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditMode::Emacs => write!(f, "emacs"),
+            EditMode::Vi => write!(f, "vi"),
+        }
+    }
+}
+
+impl std::str::FromStr for EditMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "emacs" => Ok(EditMode::Emacs),
+            "vi" => Ok(EditMode::Vi),
+            _ => Err(format!("Invalid edit mode: {s} (expected emacs or vi)")),
+        }
+    }
+}
+
+/// When results are shown as one block of `column | value` lines per record
+/// instead of a grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpandedMode {
+    #[default]
+    Off,
+    On,
+    /// Only when the grid would be wider than the terminal
+    Auto,
+}
+
+impl std::fmt::Display for ExpandedMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpandedMode::Off => write!(f, "off"),
+            ExpandedMode::On => write!(f, "on"),
+            ExpandedMode::Auto => write!(f, "auto"),
         }
     }
 }
 
+impl std::str::FromStr for ExpandedMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(ExpandedMode::Off),
+            "on" => Ok(ExpandedMode::On),
+            "auto" => Ok(ExpandedMode::Auto),
+            _ => Err(format!(
+                "Invalid expanded mode: {s} (expected on, off or auto)"
+            )),
+        }
+    }
+}
+
+/// How results are written out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayOptions {
+    pub expanded: ExpandedMode,
+    /// Send output taller or wider than the terminal through the pager
+    pub pager: bool,
+    /// Dialect of CSV output
+    pub csv: CsvOptions,
+    /// Print query results in batches of this many rows as they arrive,
+    /// instead of once the whole result has been read (0); see
+    /// [`DisplayOptions::fetch_count`] when not set
+    pub fetch_count: Option<usize>,
+    /// Report how long each statement took and how much it returned
+    pub timing: bool,
+    /// With `timing`, also report the server's figures for each query
+    pub stats: bool,
+}
+
+/// Rows per batch when `--fetch-count` is not given.
+pub const DEFAULT_FETCH_COUNT: usize = 1000;
+
+impl DisplayOptions {
+    /// Rows to print at a time in `format`, 0 for the whole result at once.
+    /// Unless set, results stream in every format but `table`, whose column
+    /// widths depend on all the rows.
+    pub fn fetch_count(&self, format: &OutputFormat) -> usize {
+        self.fetch_count.unwrap_or(match format {
+            OutputFormat::Table => 0,
+            _ => DEFAULT_FETCH_COUNT,
+        })
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Connect to PostgreSQL and show connection info
@@ -48,10 +175,31 @@ pub enum Commands {
     Crud(CrudCommands),
 
     /// Execute a Custom SQL Query
+    #[command(group(ArgGroup::new("input").required(true).args(["sql", "file"])))]
     Query {
-        /// SQL Query to Execute
+        /// SQL Query to Execute (several statements may be separated by ';')
         #[arg(short, long)]
-        sql: String,
+        sql: Option<String>,
+
+        /// File of SQL statements to execute, '-' for stdin (may be repeated)
+        #[arg(short, long, value_name = "FILE")]
+        file: Vec<PathBuf>,
+
+        /// Value for the next $n placeholder (may be repeated)
+        #[arg(long, value_name = "VALUE")]
+        param: Vec<String>,
+
+        /// Type of the next $n placeholder, e.g. int8 (inferred when not given)
+        #[arg(long, value_name = "TYPE")]
+        param_type: Vec<String>,
+
+        /// Run all statements in one transaction, rolled back if one fails
+        #[arg(long, conflicts_with = "continue_on_error")]
+        single_transaction: bool,
+
+        /// Go on with the next statement when one fails, instead of stopping
+        #[arg(long)]
+        continue_on_error: bool,
 
         /// Target Database (overrides global database)
         #[arg(short, long)]
@@ -63,7 +211,23 @@ pub enum Commands {
         /// Target Database
         #[arg(short, long)]
         database: Option<String>,
+
+        /// Key bindings for line editing (emacs, vi)
+        #[arg(long, env = "DCON_EDIT_MODE", default_value_t = EditMode::Emacs)]
+        edit_mode: EditMode,
+
+        /// Do not read or write the history file
+        #[arg(long)]
+        no_history: bool,
+
+        /// Keep statements that contain passwords out of the history
+        #[arg(long, env = "DCON_HIDE_SECRETS")]
+        hide_secrets: bool,
     },
+
+    /// Manage named connection profiles
+    #[command(subcommand)]
+    Profile(ProfileCommands),
 }
 
 #[derive(Subcommand)]
@@ -99,7 +263,7 @@ pub enum DatabaseCommands {
 
     /// Show database Information
     Info {
-        /// Database name (uses current if not specified)
+        /// Database name (uses the connection database if not specified)
         #[arg(short, long)]
         name: Option<String>,
     },
@@ -120,9 +284,9 @@ pub enum TableCommands {
 
     /// Describe table structure
     Describe {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(short, long)]
-        table: String,
+        table: QualifiedName,
 
         /// Target Database (overrides global database)
         #[arg(short, long)]
@@ -141,9 +305,9 @@ pub enum TableCommands {
     },
 
     Drop {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(long, short)]
-        table: String,
+        table: QualifiedName,
 
         /// Target Database (overrides global database)
         #[arg(short, long)]
@@ -159,24 +323,24 @@ pub enum TableCommands {
 pub enum CrudCommands {
     /// Create/Insert Data
     Create {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(short, long)]
-        table: String,
+        table: QualifiedName,
 
         /// JSON data to insert
         #[arg(short, long)]
         data: String,
 
         /// Target Database (overrides global database)
-        #[arg(short, long)]
+        #[arg(long)]
         database: Option<String>,
     },
 
     /// Read/Select Data
     Read {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(short, long)]
-        table: String,
+        table: QualifiedName,
 
         /// WHERE clause filter
         #[arg(short, long)]
@@ -195,7 +359,7 @@ pub enum CrudCommands {
         offset: Option<i64>,
 
         /// ORDER BY clause
-        #[arg(short = 'o', long)]
+        #[arg(long)]
         order: Option<String>,
 
         /// Target Database (overrides global database)
@@ -205,9 +369,9 @@ pub enum CrudCommands {
 
     /// Update data
     Update {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(short, long)]
-        table: String,
+        table: QualifiedName,
 
         /// JSON data with new values
         #[arg(short, long)]
@@ -218,7 +382,7 @@ pub enum CrudCommands {
         filter: String,
 
         /// Target Database (overrides global database)
-        #[arg(short, long)]
+        #[arg(long)]
         database: Option<String>,
 
         /// Skip confirmation prompt
@@ -228,9 +392,9 @@ pub enum CrudCommands {
 
     /// Delete Data
     Delete {
-        /// Table name
+        /// Table name, optionally schema-qualified (schema.table)
         #[arg(short, long)]
-        table: String,
+        table: QualifiedName,
 
         /// WHERE clause (required for safety)
         #[arg(short, long)]
@@ -245,3 +409,85 @@ pub enum CrudCommands {
         confirm: bool,
     },
 }
+
+// Parsed once per run, so the size of `Add` does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// List saved profiles
+    List,
+
+    /// Show the settings of a profile
+    Show {
+        /// Profile name
+        name: String,
+    },
+
+    /// Add or replace a profile
+    Add {
+        /// Profile name
+        name: String,
+
+        /// Database host
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+
+        /// Database port
+        #[arg(short = 'P', long)]
+        port: Option<u16>,
+
+        /// Username
+        #[arg(short, long)]
+        user: Option<String>,
+
+        /// Database name
+        #[arg(short, long)]
+        database: Option<String>,
+
+        /// SSL mode (disable, prefer, require, verify-ca, verify-full)
+        #[arg(long)]
+        sslmode: Option<SslMode>,
+
+        /// Root certificate file used to verify the server certificate
+        #[arg(long)]
+        sslrootcert: Option<String>,
+
+        /// Client certificate file
+        #[arg(long)]
+        sslcert: Option<String>,
+
+        /// Client private key file (PKCS#8 PEM)
+        #[arg(long)]
+        sslkey: Option<String>,
+
+        /// Schema searched first for unqualified table names
+        #[arg(long)]
+        schema: Option<String>,
+
+        /// Session search_path
+        #[arg(long)]
+        search_path: Option<String>,
+
+        /// Default output format for this profile
+        #[arg(long)]
+        format: Option<OutputFormat>,
+
+        /// Disable colored output for this profile
+        #[arg(long)]
+        no_color: bool,
+
+        /// Use this profile when --profile is not given
+        #[arg(long)]
+        default: bool,
+
+        /// Replace an existing profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove a profile
+    Remove {
+        /// Profile name
+        name: String,
+    },
+}
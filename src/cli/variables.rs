@@ -0,0 +1,360 @@
+use crate::db::params::QueryParams;
+use crate::error::DconError;
+use crate::models::table::quote_ident;
+use std::collections::{BTreeMap, HashMap};
+
+/// Variables set with `--var` or `\set`, referred to in SQL as `:name`.
+pub type Variables = BTreeMap<String, String>;
+
+/// Parses the `NAME=VALUE` argument of `--var`.
+pub fn parse_assignment(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got \"{s}\""))?;
+    if !is_valid_name(name) {
+        return Err(format!("invalid variable name \"{name}\""));
+    }
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Whether `name` can be referred to as `:name`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(is_name_byte)
+}
+
+/// Part of a statement, split at variable references.
+enum Piece<'a> {
+    Text(&'a str),
+    /// `:name` or `:'name'`
+    Value(&'a str),
+    /// `:"name"`
+    Identifier(&'a str),
+}
+
+/// Prepares a statement for binding: references to variables are replaced
+/// the way psql does, except that values are never written into the SQL.
+///
+/// `:name` and `:'name'` become placeholders numbered after the highest
+/// `$n` of the statement, bound to the variable's value. `:"name"` stands
+/// where a value cannot, for a table or column name, and is written as a
+/// quoted identifier. References to undefined variables, and anything in
+/// string literals, quoted identifiers, dollar-quoted bodies and comments, are
+/// left alone. Of `params`, only the values for the `$n` the statement uses
+/// are kept.
+pub fn bind(
+    sql: &str,
+    params: &QueryParams,
+    variables: &Variables,
+) -> Result<(String, QueryParams), DconError> {
+    let (pieces, highest) = split(sql, variables);
+    if highest > params.values.len() {
+        return Err(DconError::UserInput(format!(
+            "No value given for parameter ${}",
+            params.values.len() + 1
+        )));
+    }
+
+    let mut bound = QueryParams {
+        values: params.values[..highest].to_vec(),
+        types: params.types[..highest.min(params.types.len())].to_vec(),
+    };
+    let mut numbers: HashMap<&str, usize> = HashMap::new();
+    let mut text = String::with_capacity(sql.len());
+
+    for piece in pieces {
+        match piece {
+            Piece::Text(part) => text.push_str(part),
+            Piece::Value(name) => {
+                let number = *numbers.entry(name).or_insert_with(|| {
+                    bound.values.push(variables[name].clone());
+                    bound.values.len()
+                });
+                text.push_str(&format!("${number}"));
+            }
+            Piece::Identifier(name) => text.push_str(&quote_ident(&variables[name])),
+        }
+    }
+
+    Ok((text, bound))
+}
+
+/// Splits `sql` at references to defined variables, and finds the highest
+/// `$n` placeholder.
+fn split<'a>(sql: &'a str, variables: &Variables) -> (Vec<Piece<'a>>, usize) {
+    // Everything that delimits a token is ASCII, so byte offsets always fall
+    // on character boundaries.
+    let bytes = sql.as_bytes();
+    let at = |i: usize| bytes.get(i).copied().unwrap_or_default();
+    let mut pieces = Vec::new();
+    let mut highest = 0;
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let follows_word = i > 0 && is_word_byte(bytes[i - 1]);
+        match bytes[i] {
+            b'\'' => {
+                // E'...' strings take backslash escapes.
+                let escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_word_byte(bytes[i - 2]));
+                i = skip_quoted(bytes, i, b'\'', escapes);
+            }
+            b'"' => i = skip_quoted(bytes, i, b'"', false),
+            b'-' if at(i + 1) == b'-' => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end + 1);
+            }
+            b'/' if at(i + 1) == b'*' => i = skip_block_comment(bytes, i),
+            b'$' if !follows_word => {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                if digits > 0 {
+                    let number = sql[i + 1..i + 1 + digits].parse().unwrap_or(usize::MAX);
+                    highest = highest.max(number);
+                    i += 1 + digits;
+                } else if let Some(tag_len) = dollar_tag(&bytes[i..]) {
+                    let tag = &sql[i..i + tag_len];
+                    let body = i + tag_len;
+                    i = sql[body..]
+                        .find(tag)
+                        .map_or(bytes.len(), |end| body + end + tag_len);
+                } else {
+                    i += 1;
+                }
+            }
+            // `::` is a cast.
+            b':' if at(i + 1) == b':' => i += 2,
+            b':' => {
+                let (name, end, piece): (&str, usize, fn(&'a str) -> Piece<'a>) = match at(i + 1) {
+                    quote @ (b'\'' | b'"') => {
+                        let Some(len) = bytes[i + 2..].iter().position(|&b| b == quote) else {
+                            i += 1;
+                            continue;
+                        };
+                        let piece = if quote == b'"' {
+                            Piece::Identifier
+                        } else {
+                            Piece::Value
+                        };
+                        (&sql[i + 2..i + 2 + len], i + 3 + len, piece)
+                    }
+                    _ => {
+                        let len = bytes[i + 1..]
+                            .iter()
+                            .take_while(|&&b| is_name_byte(b))
+                            .count();
+                        (&sql[i + 1..i + 1 + len], i + 1 + len, Piece::Value)
+                    }
+                };
+
+                if is_valid_name(name) && variables.contains_key(name) {
+                    pieces.push(Piece::Text(&sql[start..i]));
+                    pieces.push(piece(name));
+                    start = end;
+                    i = end;
+                } else {
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    pieces.push(Piece::Text(&sql[start..]));
+
+    (pieces, highest)
+}
+
+/// The end of the quoted text starting at `start`, where doubled quotes
+/// (and, with `escapes`, backslashes) do not close it.
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escapes => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// The end of the (possibly nested) block comment starting at `start`.
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'/', b'*', ..] => {
+                depth += 1;
+                i += 2;
+            }
+            [b'*', b'/', ..] => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// Length of the `$tag$` opening a dollar-quoted string, if `bytes` starts
+/// with one.
+fn dollar_tag(bytes: &[u8]) -> Option<usize> {
+    let len = bytes[1..]
+        .iter()
+        .take_while(|&&b| is_word_byte(b) && b != b'$')
+        .count();
+    let starts_like_identifier = len == 0 || !bytes[1].is_ascii_digit();
+
+    (starts_like_identifier && bytes.get(1 + len) == Some(&b'$')).then_some(len + 2)
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Bytes that can continue an identifier or keyword, including those of
+/// non-ASCII letters.
+fn is_word_byte(b: u8) -> bool {
+    is_name_byte(b) || b == b'$' || !b.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> Variables {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn params(values: &[&str]) -> QueryParams {
+        QueryParams {
+            values: values.iter().map(|value| value.to_string()).collect(),
+            types: Vec::new(),
+        }
+    }
+
+    fn bound(sql: &str, vars: &[(&str, &str)]) -> (String, Vec<String>) {
+        let (text, params) = bind(sql, &QueryParams::default(), &variables(vars)).expect("bind");
+        (text, params.values)
+    }
+
+    #[test]
+    fn values_become_parameters() {
+        assert_eq!(
+            bound("select :a, :'b'", &[("a", "1"), ("b", "x y")]),
+            (
+                "select $1, $2".to_string(),
+                vec!["1".to_string(), "x y".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn identifiers_are_quoted_into_the_text() {
+        assert_eq!(
+            bound(r#"select * from :"t""#, &[("t", r#"my "table""#)]),
+            (r#"select * from "my ""table""""#.to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn reused_variables_share_a_parameter() {
+        assert_eq!(
+            bound("select :a, :a, :'a'", &[("a", "1")]),
+            ("select $1, $1, $1".to_string(), vec!["1".to_string()])
+        );
+    }
+
+    #[test]
+    fn casts_are_not_references() {
+        assert_eq!(
+            bound("select x::int, :v::int", &[("int", "no"), ("v", "5")]),
+            ("select x::int, $1::int".to_string(), vec!["5".to_string()])
+        );
+    }
+
+    #[test]
+    fn quoted_text_and_comments_are_left_alone() {
+        let vars = [("a", "1")];
+        for sql in [
+            "select ':a'",
+            "select E'\\' :a'",
+            r#"select ":a""#,
+            "select $$ :a $$",
+            "select $f$ :a $f$",
+            "select 1 -- :a",
+            "select /* :a /* :a */ :a */ 1",
+        ] {
+            assert_eq!(bound(sql, &vars), (sql.to_string(), vec![]), "{sql}");
+        }
+    }
+
+    #[test]
+    fn undefined_variables_are_left_alone() {
+        assert_eq!(
+            bound("select :missing, :'missing', :a", &[("a", "1")]),
+            (
+                "select :missing, :'missing', $1".to_string(),
+                vec!["1".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn numbering_follows_the_highest_placeholder() {
+        let (text, bound) = bind(
+            "select $2, :a, $1",
+            &params(&["x", "y"]),
+            &variables(&[("a", "1")]),
+        )
+        .expect("bind");
+        assert_eq!(text, "select $2, $3, $1");
+        assert_eq!(bound.values, ["x", "y", "1"]);
+    }
+
+    #[test]
+    fn unused_parameters_are_dropped() {
+        let mut given = params(&["x", "y", "z"]);
+        given.types = vec!["int4".to_string(), "text".to_string()];
+        let (text, bound) = bind("select $1, :a", &given, &variables(&[("a", "1")])).expect("bind");
+        assert_eq!(text, "select $1, $2");
+        assert_eq!(bound.values, ["x", "1"]);
+        assert_eq!(bound.types, ["int4"]);
+
+        let (_, bound) = bind("select 1", &given, &Variables::new()).expect("bind");
+        assert!(bound.values.is_empty());
+        assert!(bound.types.is_empty());
+    }
+
+    #[test]
+    fn missing_parameters_are_an_error() {
+        let error = bind("select $1, $3", &params(&["x"]), &Variables::new()).unwrap_err();
+        assert_eq!(error.to_string(), "No value given for parameter $2");
+    }
+
+    #[test]
+    fn assignments() {
+        assert_eq!(
+            parse_assignment("a_1=x=y"),
+            Ok(("a_1".to_string(), "x=y".to_string()))
+        );
+        assert_eq!(parse_assignment("a="), Ok(("a".to_string(), String::new())));
+        assert!(parse_assignment("novalue").is_err());
+        assert!(parse_assignment("bad-name=1").is_err());
+        assert!(parse_assignment("=1").is_err());
+    }
+}
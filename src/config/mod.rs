@@ -0,0 +1,129 @@
+pub mod pgpass;
+pub mod service;
+
+use crate::error::DconError;
+use crate::models::profile::{DconConfig, Profile};
+use std::fs;
+use std::path::PathBuf;
+
+/// Location of the configuration file: `$DCON_CONFIG` if set, otherwise
+/// `$XDG_CONFIG_HOME/dcon/config.toml`, falling back to
+/// `~/.config/dcon/config.toml`.
+pub fn config_path() -> Result<PathBuf, DconError> {
+    if let Some(path) = std::env::var_os("DCON_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir()
+            .ok_or_else(|| {
+                DconError::UserInput("Could not determine the home directory".to_string())
+            })?
+            .join(".config"),
+    };
+
+    Ok(base.join("dcon").join("config.toml"))
+}
+
+/// Directory for data dcon keeps between runs, such as interactive history:
+/// `$DCON_DATA_DIR` if set, otherwise `dcon` under the platform data
+/// directory (`$XDG_DATA_HOME` or `~/.local/share` on Linux).
+pub fn data_dir() -> Result<PathBuf, DconError> {
+    if let Some(path) = std::env::var_os("DCON_DATA_DIR") {
+        return Ok(PathBuf::from(path));
+    }
+
+    dirs::data_dir()
+        .map(|dir| dir.join("dcon"))
+        .ok_or_else(|| DconError::UserInput("Could not determine the data directory".to_string()))
+}
+
+/// Loads the configuration file, returning an empty configuration if it
+/// does not exist yet.
+pub fn load_config() -> Result<DconConfig, DconError> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(DconConfig::default());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| {
+        DconError::io(
+            e,
+            format!("Failed to read config file '{}'", path.display()),
+        )
+    })?;
+    let config = toml::from_str(&contents).map_err(|e| {
+        DconError::UserInput(format!("Invalid config file '{}': {e}", path.display()))
+    })?;
+
+    Ok(config)
+}
+
+pub fn save_config(config: &DconConfig) -> Result<PathBuf, DconError> {
+    let path = config_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            DconError::io(
+                e,
+                format!("Failed to create config directory '{}'", parent.display()),
+            )
+        })?;
+    }
+
+    let contents = toml::to_string_pretty(config)
+        .map_err(|e| DconError::UserInput(format!("Failed to serialize config: {e}")))?;
+    fs::write(&path, contents).map_err(|e| {
+        DconError::io(
+            e,
+            format!("Failed to write config file '{}'", path.display()),
+        )
+    })?;
+
+    Ok(path)
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Connection settings of the service named by `PGSERVICE`, if any.
+pub fn service_profile() -> Result<Profile, DconError> {
+    match env_var("PGSERVICE") {
+        Some(name) => service::load_service(&name),
+        None => Ok(Profile::default()),
+    }
+}
+
+/// Connection settings taken from the standard libpq environment variables.
+pub fn env_profile() -> Result<Profile, DconError> {
+    let var = env_var;
+
+    let port = match var("PGPORT") {
+        Some(port) => Some(
+            port.parse()
+                .map_err(|_| DconError::UserInput(format!("Invalid PGPORT value: {port}")))?,
+        ),
+        None => None,
+    };
+    let sslmode = match var("PGSSLMODE") {
+        Some(mode) => Some(mode.parse().map_err(DconError::UserInput)?),
+        None => None,
+    };
+
+    Ok(Profile {
+        host: var("PGHOST"),
+        port,
+        user: var("PGUSER"),
+        database: var("PGDATABASE"),
+        password: var("PGPASSWORD"),
+        sslmode,
+        sslrootcert: var("PGSSLROOTCERT"),
+        sslcert: var("PGSSLCERT"),
+        sslkey: var("PGSSLKEY"),
+        schema: None,
+        search_path: None,
+        format: None,
+        color: None,
+    })
+}
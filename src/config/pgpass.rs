@@ -0,0 +1,226 @@
+use crate::error::DconError;
+use crate::models::connection::ConnectionConfig;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Location of the password file: `$PGPASSFILE`, otherwise `~/.pgpass`
+/// (`%APPDATA%\postgresql\pgpass.conf` on Windows).
+pub fn pgpass_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("PGPASSFILE").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+
+    if cfg!(windows) {
+        dirs::config_dir().map(|dir| dir.join("postgresql").join("pgpass.conf"))
+    } else {
+        dirs::home_dir().map(|dir| dir.join(".pgpass"))
+    }
+}
+
+/// Looks up the password for `config` in the password file, following the
+/// libpq rules: the first line whose host, port, database and user fields
+/// match (or are `*`) wins, and a file readable by group or others is
+/// ignored with a warning.
+pub fn lookup_password(config: &ConnectionConfig) -> Result<Option<String>, DconError> {
+    let Some(path) = pgpass_path() else {
+        return Ok(None);
+    };
+    let Ok(metadata) = fs::metadata(&path) else {
+        return Ok(None);
+    };
+
+    if !metadata.is_file() {
+        eprintln!(
+            "WARNING: password file \"{}\" is not a plain file",
+            path.display()
+        );
+        return Ok(None);
+    }
+    if !has_safe_permissions(&metadata) {
+        eprintln!(
+            "WARNING: password file \"{}\" has group or world access; permissions should be u=rw (0600) or less",
+            path.display()
+        );
+        return Ok(None);
+    }
+
+    let contents = read_pgpass(&path)?;
+
+    // Unix-domain socket connections are matched against "localhost".
+    let host = if config.host.starts_with('/') {
+        "localhost"
+    } else {
+        config.host.as_str()
+    };
+    let port = config.port.to_string();
+    let wanted = [
+        host,
+        port.as_str(),
+        config.database.as_str(),
+        config.user.as_str(),
+    ];
+
+    for line in contents.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let Some((fields, password)) = split_fields(line) else {
+            continue;
+        };
+
+        let matches = fields
+            .iter()
+            .zip(wanted)
+            .all(|(field, value)| field.matches(value));
+        if matches {
+            return Ok(Some(password));
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_pgpass(path: &Path) -> Result<String, DconError> {
+    fs::read_to_string(path).map_err(|e| {
+        DconError::io(
+            e,
+            format!("Failed to read password file '{}'", path.display()),
+        )
+    })
+}
+
+#[cfg(unix)]
+fn has_safe_permissions(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o077 == 0
+}
+
+#[cfg(not(unix))]
+fn has_safe_permissions(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+struct Field {
+    value: String,
+    wildcard: bool,
+}
+
+impl Field {
+    fn matches(&self, value: &str) -> bool {
+        self.wildcard || self.value == value
+    }
+}
+
+/// Splits a pgpass line into its host, port, database and user fields and
+/// the password, resolving `\:` and `\\`. The fields end at unescaped colons;
+/// the password is the rest of the line, colons included. Only an unescaped
+/// `*` counts as a wildcard. Lines with fewer than five fields give `None`.
+fn split_fields(line: &str) -> Option<([Field; 4], String)> {
+    let mut fields = Vec::with_capacity(4);
+    let mut value = String::new();
+    let mut escaped_any = false;
+    let mut chars = line.chars();
+
+    while fields.len() < 4 {
+        match chars.next()? {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    value.push(next);
+                    escaped_any = true;
+                }
+            }
+            ':' => {
+                fields.push(Field {
+                    wildcard: value == "*" && !escaped_any,
+                    value: std::mem::take(&mut value),
+                });
+                escaped_any = false;
+            }
+            c => value.push(c),
+        }
+    }
+
+    let mut password = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => password.extend(chars.next()),
+            c => password.push(c),
+        }
+    }
+
+    Some((fields.try_into().ok()?, password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> (Vec<(String, bool)>, String) {
+        let (fields, password) = split_fields(line).expect("five fields");
+        let fields = fields
+            .into_iter()
+            .map(|field| (field.value, field.wildcard))
+            .collect();
+        (fields, password)
+    }
+
+    #[test]
+    fn splits_plain_fields() {
+        let (fields, password) = parse("db.example.com:5432:app:alice:secret");
+        assert_eq!(
+            fields,
+            [
+                ("db.example.com".to_string(), false),
+                ("5432".to_string(), false),
+                ("app".to_string(), false),
+                ("alice".to_string(), false),
+            ]
+        );
+        assert_eq!(password, "secret");
+    }
+
+    #[test]
+    fn unescapes_colons_and_backslashes() {
+        let (fields, password) = parse(r"a\:b:5432:d\\b:u:p\:w\\d");
+        assert_eq!(fields[0].0, "a:b");
+        assert_eq!(fields[2].0, r"d\b");
+        assert_eq!(password, r"p:w\d");
+    }
+
+    #[test]
+    fn only_unescaped_star_is_a_wildcard() {
+        let (fields, _) = parse(r"*:\*:*x:*:pw");
+        assert_eq!(fields[0], ("*".to_string(), true));
+        assert_eq!(fields[1], ("*".to_string(), false));
+        assert_eq!(fields[2], ("*x".to_string(), false));
+        assert!(fields[3].1);
+    }
+
+    #[test]
+    fn password_keeps_unescaped_colons() {
+        let (_, password) = parse("localhost:5432:*:alice:pa:ss:word");
+        assert_eq!(password, "pa:ss:word");
+    }
+
+    #[test]
+    fn empty_password() {
+        let (_, password) = parse("localhost:5432:*:alice:");
+        assert_eq!(password, "");
+    }
+
+    #[test]
+    fn rejects_short_lines() {
+        assert!(split_fields("localhost:5432:app:alice").is_none());
+        assert!(split_fields("").is_none());
+    }
+
+    #[test]
+    fn wildcard_matches_anything() {
+        let (fields, _) = split_fields("*:5432:app:*:pw").expect("five fields");
+        assert!(fields[0].matches("anything"));
+        assert!(fields[1].matches("5432"));
+        assert!(!fields[1].matches("5433"));
+        assert!(fields[3].matches("bob"));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::error::DconError;
+use crate::models::profile::Profile;
+use std::fs;
+use std::path::PathBuf;
+
+/// Candidate service files in lookup order: the per-user file
+/// (`$PGSERVICEFILE` or `~/.pg_service.conf`), then the system-wide
+/// `$PGSYSCONFDIR/pg_service.conf`.
+fn service_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    if let Some(path) = std::env::var_os("PGSERVICEFILE").filter(|p| !p.is_empty()) {
+        files.push(PathBuf::from(path));
+    } else if let Some(home) = dirs::home_dir() {
+        files.push(home.join(".pg_service.conf"));
+    }
+
+    if let Some(dir) = std::env::var_os("PGSYSCONFDIR").filter(|p| !p.is_empty()) {
+        files.push(PathBuf::from(dir).join("pg_service.conf"));
+    }
+
+    files
+}
+
+/// Reads the settings of a connection service, as selected by `PGSERVICE`.
+/// The first file that defines the service is used.
+pub fn load_service(name: &str) -> Result<Profile, DconError> {
+    for path in service_files() {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        if let Some(profile) = parse_service(&contents, name).map_err(|e| {
+            DconError::UserInput(format!("Invalid service file '{}': {e}", path.display()))
+        })? {
+            return Ok(profile);
+        }
+    }
+
+    Err(DconError::UserInput(format!(
+        "Definition of service \"{name}\" not found"
+    )))
+}
+
+fn parse_service(contents: &str, name: &str) -> Result<Option<Profile>, String> {
+    let mut profile = None;
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[') {
+            if profile.is_some() {
+                break;
+            }
+            if section.trim_end_matches(']') == name {
+                profile = Some(Profile::default());
+            }
+            continue;
+        }
+
+        let Some(profile) = profile.as_mut() else {
+            continue;
+        };
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("syntax error on line {}", line_number + 1))?;
+        let value = value.trim().to_string();
+
+        match key.trim() {
+            "host" | "hostaddr" => profile.host = Some(value),
+            "port" => {
+                profile.port = Some(value.parse().map_err(|_| {
+                    format!("invalid port \"{value}\" on line {}", line_number + 1)
+                })?);
+            }
+            "user" => profile.user = Some(value),
+            "dbname" => profile.database = Some(value),
+            "password" => profile.password = Some(value),
+            "sslmode" => profile.sslmode = Some(value.parse()?),
+            "sslrootcert" => profile.sslrootcert = Some(value),
+            "sslcert" => profile.sslcert = Some(value),
+            "sslkey" => profile.sslkey = Some(value),
+            _ => {}
+        }
+    }
+
+    Ok(profile)
+}
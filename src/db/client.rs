@@ -1,45 +1,191 @@
+use crate::cli::splitter::is_read_only;
+use crate::db::params::{placeholder, JsonParam, QueryParams, TextParam};
+use crate::db::tls::make_tls_connector;
+use crate::db::value::{column_value, needs_text, row_size};
+use crate::error::DconError;
+use crate::interrupt;
+use crate::models::catalog::{
+    CatalogNames, CatalogObject, FunctionInfo, IndexSummary, NamePattern, RelationInfo, RoleInfo,
+    SchemaInfo,
+};
 use crate::models::connection::{ConnectionConfig, ConnectionInfo};
-use crate::models::database::DatabaseInfo;
-use crate::models::table::{ColumnInfo, TableInfo};
-use chrono::{NaiveDate, NaiveDateTime};
+use crate::models::database::{DatabaseDetails, DatabaseInfo, ExtensionInfo, RelationSize};
+use crate::models::table::{
+    ColumnInfo, ConstraintInfo, ForeignKeyInfo, IndexInfo, PartitionInfo, PolicyInfo,
+    QualifiedName, TableDetails, TableInfo, TriggerInfo,
+};
+use crate::models::timing::{QueryTiming, ServerStats};
+use crate::status;
 use colored::*;
+use futures_util::TryStreamExt;
 use serde_json::Value;
-use tokio_postgres::{Client, NoTls, Row};
+use std::future::Future;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_postgres::types::{Kind, Type};
+use tokio_postgres::{CancelToken, Client, Column, Connection, NoTls, Row, Statement};
 
 pub struct PostgresClient {
     client: Client,
     config: ConnectionConfig,
 }
 
-impl PostgresClient {
-    pub async fn new(config: &ConnectionConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let connection_string = config.to_connection_string();
+/// Outcome of a single statement: the rows it returned, its prepared form
+/// (which describes the result columns, even when no rows came back), for
+/// commands such as INSERT the number of rows it affected, and how long it
+/// took.
+///
+/// Results of [`PostgresClient::stream_query`] carry no rows, since those
+/// have been handed over as they arrived.
+pub struct QueryResult {
+    pub statement: Statement,
+    pub rows: Vec<Row>,
+    pub rows_affected: Option<u64>,
+    pub timing: QueryTiming,
+}
 
-        println!("{}", "🔌 Connecting to PostgreSQL...".cyan());
+impl QueryResult {
+    pub fn columns(&self) -> &[Column] {
+        self.statement.columns()
+    }
+}
 
-        let (client, connection) = tokio_postgres::connect(&connection_string, NoTls)
-            .await
-            .map_err(|e| format!("Failed to connect to PostgreSQL: {e}"))
-            .unwrap();
+/// Cancels the statement running on a connection, through a connection of
+/// its own; it can be used while the client is busy.
+pub struct Canceller {
+    token: CancelToken,
+    /// For the TLS settings of the cancel connection
+    config: ConnectionConfig,
+}
+
+impl Canceller {
+    /// Runs `work`, and if Ctrl-C is pressed before it ends asks the server
+    /// to cancel whatever statement is running. That statement then fails,
+    /// which is reported as [`DconError::Cancelled`].
+    pub async fn run<T>(
+        &self,
+        work: impl Future<Output = Result<T, DconError>>,
+    ) -> Result<T, DconError> {
+        let mut running = interrupt::Running::start();
+        tokio::pin!(work);
+        tokio::select! {
+            result = &mut work => return result,
+            () = running.interrupted() => {}
+        }
 
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("Connection Error: {e}");
+        status!("{}", "Cancel request sent".yellow());
+        if let Err(e) = self.cancel().await {
+            eprintln!("{}", e.report().red());
+        }
+
+        match work.await {
+            // 57014: query_canceled
+            Err(DconError::Sql(error)) if error.code.as_deref() == Some("57014") => {
+                Err(DconError::Cancelled)
             }
-        });
+            result => result,
+        }
+    }
+
+    async fn cancel(&self) -> Result<(), DconError> {
+        let result = if self.config.sslmode.uses_tls() {
+            self.token
+                .cancel_query(make_tls_connector(&self.config)?)
+                .await
+        } else {
+            self.token.cancel_query(NoTls).await
+        };
+
+        result.map_err(DconError::from_connect)
+    }
+}
+
+impl PostgresClient {
+    pub async fn new(config: &ConnectionConfig) -> Result<Self, DconError> {
+        let connection_string = config.to_connection_string();
+
+        status!("{}", "🔌 Connecting to PostgreSQL...".cyan());
+
+        let client = if config.sslmode.uses_tls() {
+            let tls = make_tls_connector(config)?;
+            let (client, connection) = tokio_postgres::connect(&connection_string, tls)
+                .await
+                .map_err(DconError::from_connect)?;
+            spawn_connection(connection);
+            client
+        } else {
+            let (client, connection) = tokio_postgres::connect(&connection_string, NoTls)
+                .await
+                .map_err(DconError::from_connect)?;
+            spawn_connection(connection);
+            client
+        };
 
-        println!("{}", "✅ Connected successfully!".green());
+        status!("{}", "✅ Connected successfully!".green());
 
-        Ok(PostgresClient {
+        let client = PostgresClient {
             client,
             config: config.clone(),
-        })
+        };
+        client.apply_search_path().await?;
+        client.apply_timeouts().await?;
+
+        Ok(client)
     }
 
-    pub async fn get_connection_info(
-        &self,
-    ) -> Result<Vec<ConnectionInfo>, Box<dyn std::error::Error>> {
-        println!("{}", "ℹ️  Fetching connection information...".cyan());
+    /// The settings this client connected with.
+    pub fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
+
+    /// Sets the session's `search_path` from `--search-path` and puts the
+    /// `--schema` schema in front of it.
+    async fn apply_search_path(&self) -> Result<(), DconError> {
+        if self.config.schema.is_none() && self.config.search_path.is_none() {
+            return Ok(());
+        }
+
+        let query = r#"
+            SELECT pg_catalog.set_config(
+                'search_path',
+                concat_ws(', ',
+                    pg_catalog.quote_ident($1),
+                    coalesce($2, pg_catalog.current_setting('search_path'))),
+                false)
+        "#;
+
+        self.client
+            .query_one(query, &[&self.config.schema, &self.config.search_path])
+            .await
+            .map_err(|e| DconError::from(e).with_statement(query.trim()))?;
+
+        Ok(())
+    }
+
+    /// Sets the `--statement-timeout`, `--lock-timeout` and
+    /// `--idle-in-transaction-timeout` limits for the session.
+    async fn apply_timeouts(&self) -> Result<(), DconError> {
+        let query = "SELECT pg_catalog.set_config($1, $2, false)";
+        for (name, value) in self.config.timeouts.settings() {
+            self.client
+                .query_one(query, &[&name, &value])
+                .await
+                .map_err(|e| DconError::from(e).with_statement(query))?;
+        }
+
+        Ok(())
+    }
+
+    /// A handle for cancelling the statement running on this connection.
+    pub fn canceller(&self) -> Canceller {
+        Canceller {
+            token: self.client.cancel_token(),
+            config: self.config.clone(),
+        }
+    }
+
+    pub async fn get_connection_info(&self) -> Result<Vec<ConnectionInfo>, DconError> {
+        status!("{}", "ℹ️  Fetching connection information...".cyan());
         let port = format!("SELECT '{}'", self.config.port);
         let host = format!("SELECT '{}'", self.config.host);
 
@@ -77,8 +223,8 @@ impl PostgresClient {
         Ok(info)
     }
 
-    pub async fn list_databases(&self) -> Result<Vec<DatabaseInfo>, Box<dyn std::error::Error>> {
-        println!("{}", "📊 Fetching database information...".cyan());
+    pub async fn list_databases(&self) -> Result<Vec<DatabaseInfo>, DconError> {
+        status!("{}", "📊 Fetching database information...".cyan());
 
         let query = r#"
             SELECT
@@ -93,7 +239,7 @@ impl PostgresClient {
             ORDER BY d.datname;
         "#;
 
-        let rows = self.client.query(query, &[]).await.unwrap();
+        let rows = self.client.query(query, &[]).await?;
         let databases: Vec<DatabaseInfo> = rows
             .into_iter()
             .map(|row| DatabaseInfo {
@@ -108,11 +254,349 @@ impl PostgresClient {
         Ok(databases)
     }
 
-    pub async fn list_tables(
+    /// Details of the database this client is connected to.
+    pub async fn get_database_details(&self) -> Result<DatabaseDetails, DconError> {
+        status!(
+            "{}",
+            format!(
+                "📊 Fetching details for database '{}'...",
+                self.config.database
+            )
+            .cyan()
+        );
+
+        let query = r#"
+            SELECT
+                d.datname as name,
+                pg_catalog.pg_get_userbyid(d.datdba) as owner,
+                pg_catalog.pg_encoding_to_char(d.encoding) as encoding,
+                d.datcollate as collation,
+                d.datctype as ctype,
+                t.spcname as tablespace,
+                d.datconnlimit as connection_limit,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_database_size(d.oid)) as size,
+                pg_catalog.pg_database_size(d.oid) as size_bytes,
+                pg_catalog.age(d.datfrozenxid) as frozen_xid_age,
+                COALESCE(s.numbackends, 0) as connections,
+                COALESCE(s.xact_commit, 0) as xact_commit,
+                COALESCE(s.xact_rollback, 0) as xact_rollback,
+                round(100.0 * s.blks_hit / NULLIF(s.blks_hit + s.blks_read, 0), 2)::float8
+                    as cache_hit_ratio,
+                COALESCE(s.deadlocks, 0) as deadlocks,
+                COALESCE(s.temp_files, 0) as temp_files,
+                pg_catalog.pg_size_pretty(COALESCE(s.temp_bytes, 0)) as temp_bytes,
+                s.stats_reset::text as stats_reset
+            FROM pg_catalog.pg_database d
+            JOIN pg_catalog.pg_tablespace t ON t.oid = d.dattablespace
+            LEFT JOIN pg_catalog.pg_stat_database s ON s.datid = d.oid
+            WHERE d.datname = pg_catalog.current_database();
+        "#;
+
+        let row = self.client.query_one(query, &[]).await?;
+
+        Ok(DatabaseDetails {
+            name: row.get("name"),
+            owner: row.get("owner"),
+            encoding: row.get("encoding"),
+            collation: row.get("collation"),
+            ctype: row.get("ctype"),
+            tablespace: row.get("tablespace"),
+            connection_limit: row.get("connection_limit"),
+            size: row.get("size"),
+            size_bytes: row.get("size_bytes"),
+            frozen_xid_age: row.get("frozen_xid_age"),
+            connections: row.get("connections"),
+            xact_commit: row.get("xact_commit"),
+            xact_rollback: row.get("xact_rollback"),
+            cache_hit_ratio: row.get("cache_hit_ratio"),
+            deadlocks: row.get("deadlocks"),
+            temp_files: row.get("temp_files"),
+            temp_bytes: row.get("temp_bytes"),
+            stats_reset: row.get("stats_reset"),
+            largest_relations: self.largest_relations(10).await?,
+            extensions: self.list_extensions(None).await?,
+        })
+    }
+
+    async fn largest_relations(&self, limit: i64) -> Result<Vec<RelationSize>, DconError> {
+        let query = r#"
+            SELECT
+                c.oid::pg_catalog.regclass::text as name,
+                CASE c.relkind
+                    WHEN 'r' THEN 'table'
+                    WHEN 'm' THEN 'materialized view'
+                END as kind,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_total_relation_size(c.oid)) as size,
+                pg_catalog.pg_total_relation_size(c.oid) as size_bytes
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.relkind IN ('r', 'm')
+                AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+                AND n.nspname !~ '^pg_toast'
+            ORDER BY size_bytes DESC, name
+            LIMIT $1;
+        "#;
+
+        let rows = self.client.query(query, &[&limit]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RelationSize {
+                name: row.get("name"),
+                kind: row.get("kind"),
+                size: row.get("size"),
+                size_bytes: row.get("size_bytes"),
+            })
+            .collect())
+    }
+
+    /// Installed extensions, optionally only those whose name matches
+    /// `pattern`.
+    pub async fn list_extensions(
+        &self,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<ExtensionInfo>, DconError> {
+        let query = r#"
+            SELECT
+                e.extname as name,
+                e.extversion as version,
+                n.nspname as schema,
+                COALESCE(pg_catalog.obj_description(e.oid, 'pg_extension'), '') as description
+            FROM pg_catalog.pg_extension e
+            JOIN pg_catalog.pg_namespace n ON n.oid = e.extnamespace
+            WHERE $1::text IS NULL OR e.extname ~ $1
+            ORDER BY e.extname;
+        "#;
+
+        let name = pattern.map(|pattern| pattern.name.as_str());
+        let rows = self.client.query(query, &[&name]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExtensionInfo {
+                name: row.get("name"),
+                version: row.get("version"),
+                schema: row.get("schema"),
+                description: row.get("description"),
+            })
+            .collect())
+    }
+
+    /// Relations of the given `pg_class.relkind`s (for example `"rp"` for
+    /// tables), with the `\d` pattern rules described at
+    /// [`pattern_condition`].
+    pub async fn list_relations(
         &self,
-        include_system: bool,
-    ) -> Result<Vec<TableInfo>, Box<dyn std::error::Error>> {
-        println!(
+        kinds: &str,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<RelationInfo>, DconError> {
+        let query = format!(
+            r#"
+            SELECT
+                n.nspname as schema,
+                c.relname as name,
+                CASE c.relkind
+                    WHEN 'r' THEN 'table'
+                    WHEN 'p' THEN 'partitioned table'
+                    WHEN 'v' THEN 'view'
+                    WHEN 'm' THEN 'materialized view'
+                    WHEN 'S' THEN 'sequence'
+                    WHEN 'f' THEN 'foreign table'
+                END as kind,
+                pg_catalog.pg_get_userbyid(c.relowner) as owner,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_total_relation_size(c.oid)) as size
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            WHERE position(c.relkind::text in $3) > 0
+              AND {}
+            ORDER BY n.nspname, c.relname;
+            "#,
+            pattern_condition(
+                "n.nspname",
+                "c.relname",
+                "pg_catalog.pg_table_is_visible(c.oid)"
+            )
+        );
+
+        let (schema, name) = pattern_params(pattern);
+        let rows = self.client.query(&query, &[&schema, &name, &kinds]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RelationInfo {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                kind: row.get("kind"),
+                owner: row.get("owner"),
+                size: row.get("size"),
+            })
+            .collect())
+    }
+
+    pub async fn list_indexes(
+        &self,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<IndexSummary>, DconError> {
+        let query = format!(
+            r#"
+            SELECT
+                n.nspname as schema,
+                c.relname as name,
+                t.relname as table_name,
+                pg_catalog.pg_get_userbyid(c.relowner) as owner,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_relation_size(c.oid)) as size
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_catalog.pg_index i ON i.indexrelid = c.oid
+            JOIN pg_catalog.pg_class t ON t.oid = i.indrelid
+            WHERE c.relkind IN ('i', 'I')
+              AND {}
+            ORDER BY n.nspname, c.relname;
+            "#,
+            pattern_condition(
+                "n.nspname",
+                "c.relname",
+                "pg_catalog.pg_table_is_visible(c.oid)"
+            )
+        );
+
+        let (schema, name) = pattern_params(pattern);
+        let rows = self.client.query(&query, &[&schema, &name]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| IndexSummary {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                table: row.get("table_name"),
+                owner: row.get("owner"),
+                size: row.get("size"),
+            })
+            .collect())
+    }
+
+    pub async fn list_functions(
+        &self,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<FunctionInfo>, DconError> {
+        let query = format!(
+            r#"
+            SELECT
+                n.nspname as schema,
+                p.proname as name,
+                COALESCE(pg_catalog.pg_get_function_result(p.oid), '') as result_type,
+                pg_catalog.pg_get_function_arguments(p.oid) as arguments,
+                CASE p.prokind
+                    WHEN 'a' THEN 'agg'
+                    WHEN 'w' THEN 'window'
+                    WHEN 'p' THEN 'proc'
+                    ELSE 'func'
+                END as kind
+            FROM pg_catalog.pg_proc p
+            JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+            WHERE {}
+            ORDER BY n.nspname, p.proname, arguments;
+            "#,
+            pattern_condition(
+                "n.nspname",
+                "p.proname",
+                "pg_catalog.pg_function_is_visible(p.oid)"
+            )
+        );
+
+        let (schema, name) = pattern_params(pattern);
+        let rows = self.client.query(&query, &[&schema, &name]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| FunctionInfo {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                result_type: row.get("result_type"),
+                arguments: row.get("arguments"),
+                kind: row.get("kind"),
+            })
+            .collect())
+    }
+
+    /// Schemas, leaving out the system ones unless a pattern is given.
+    pub async fn list_schemas(
+        &self,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<SchemaInfo>, DconError> {
+        let query = r#"
+            SELECT
+                n.nspname as name,
+                pg_catalog.pg_get_userbyid(n.nspowner) as owner
+            FROM pg_catalog.pg_namespace n
+            WHERE CASE
+                WHEN $1::text IS NULL THEN n.nspname !~ '^pg_' AND n.nspname <> 'information_schema'
+                ELSE n.nspname ~ $1
+            END
+            ORDER BY n.nspname;
+        "#;
+
+        let name = pattern.map(|pattern| pattern.name.as_str());
+        let rows = self.client.query(query, &[&name]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SchemaInfo {
+                name: row.get("name"),
+                owner: row.get("owner"),
+            })
+            .collect())
+    }
+
+    /// Roles with their attributes and memberships, leaving out the
+    /// predefined `pg_*` roles unless a pattern is given.
+    pub async fn list_roles(
+        &self,
+        pattern: Option<&NamePattern>,
+    ) -> Result<Vec<RoleInfo>, DconError> {
+        let query = r#"
+            SELECT
+                r.rolname as name,
+                concat_ws(', ',
+                    CASE WHEN r.rolsuper THEN 'Superuser' END,
+                    CASE WHEN r.rolcreaterole THEN 'Create role' END,
+                    CASE WHEN r.rolcreatedb THEN 'Create DB' END,
+                    CASE WHEN NOT r.rolcanlogin THEN 'Cannot login' END,
+                    CASE WHEN r.rolreplication THEN 'Replication' END,
+                    CASE WHEN r.rolbypassrls THEN 'Bypass RLS' END,
+                    CASE WHEN r.rolconnlimit >= 0 THEN r.rolconnlimit || ' connections' END,
+                    CASE WHEN r.rolvaliduntil IS NOT NULL
+                        THEN 'Password valid until ' || r.rolvaliduntil END
+                ) as attributes,
+                COALESCE((
+                    SELECT string_agg(g.rolname, ', ' ORDER BY g.rolname)
+                    FROM pg_catalog.pg_auth_members m
+                    JOIN pg_catalog.pg_roles g ON g.oid = m.roleid
+                    WHERE m.member = r.oid
+                ), '') as member_of
+            FROM pg_catalog.pg_roles r
+            WHERE CASE
+                WHEN $1::text IS NULL THEN r.rolname !~ '^pg_'
+                ELSE r.rolname ~ $1
+            END
+            ORDER BY r.rolname;
+        "#;
+
+        let name = pattern.map(|pattern| pattern.name.as_str());
+        let rows = self.client.query(query, &[&name]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RoleInfo {
+                name: row.get("name"),
+                attributes: row.get("attributes"),
+                member_of: row.get("member_of"),
+            })
+            .collect())
+    }
+
+    pub async fn list_tables(&self, include_system: bool) -> Result<Vec<TableInfo>, DconError> {
+        status!(
             "{}",
             format!(
                 "📋 Fetching tables for database '{}'...",
@@ -150,7 +634,7 @@ impl PostgresClient {
             }
         );
 
-        let rows = self.client.query(&table_query, &[]).await.unwrap();
+        let rows = self.client.query(&table_query, &[]).await?;
 
         let mut tables = Vec::new();
         for row in rows {
@@ -159,7 +643,10 @@ impl PostgresClient {
             let table_type: String = row.get("table_type");
 
             let row_count = if table_type == "table" {
-                match self.get_table_row_count(&table_name, Some(&schema)).await {
+                match self
+                    .get_table_row_count(&QualifiedName::new(&schema, &table_name))
+                    .await
+                {
                     Ok(count) => count.to_string(),
                     Err(_) => "Error".to_string(),
                 }
@@ -177,48 +664,139 @@ impl PostgresClient {
         Ok(tables)
     }
 
-    pub async fn describe_table(
-        &self,
-        table_name: &str,
-        schema: Option<&str>,
-    ) -> Result<Vec<ColumnInfo>, Box<dyn std::error::Error>> {
-        let schema = schema.unwrap_or("public");
+    pub async fn describe_table(&self, table: &QualifiedName) -> Result<TableDetails, DconError> {
+        let oid = self.relation_oid(table).await?;
 
-        println!(
-            "{}",
-            format!("🔍 Describing table '{schema}.{table_name}'...").cyan()
-        );
+        status!("{}", format!("🔍 Describing table '{table}'...").cyan());
 
         let query = r#"
             SELECT
-                c.column_name,
-                c.data_type,
-                c.is_nullable,
-                COALESCE(c.column_default, 'NULL') as default_value,
+                n.nspname as schema,
+                c.relname as name,
+                CASE c.relkind
+                    WHEN 'r' THEN 'table'
+                    WHEN 'p' THEN 'partitioned table'
+                    WHEN 'v' THEN 'view'
+                    WHEN 'm' THEN 'materialized view'
+                    WHEN 'f' THEN 'foreign table'
+                    ELSE c.relkind::text
+                END as kind,
+                pg_catalog.pg_get_userbyid(c.relowner) as owner,
+                COALESCE(t.spcname, 'pg_default') as tablespace,
+                pg_catalog.obj_description(c.oid, 'pg_class') as comment,
+                CASE WHEN c.reltuples >= 0 THEN c.reltuples::bigint END as row_estimate,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_relation_size(c.oid)) as heap_size,
+                CASE WHEN c.reltoastrelid <> 0 THEN
+                    pg_catalog.pg_size_pretty(pg_catalog.pg_total_relation_size(c.reltoastrelid))
+                END as toast_size,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_indexes_size(c.oid)) as indexes_size,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_total_relation_size(c.oid)) as total_size,
+                c.relrowsecurity as row_level_security,
+                c.relforcerowsecurity as force_row_level_security,
+                CASE WHEN c.relkind = 'p' THEN pg_catalog.pg_get_partkeydef(c.oid) END as partition_key,
+                CASE WHEN c.relispartition THEN (
+                    SELECT i.inhparent::pg_catalog.regclass::text
+                    FROM pg_catalog.pg_inherits i
+                    WHERE i.inhrelid = c.oid
+                ) END as parent,
+                pg_catalog.pg_get_expr(c.relpartbound, c.oid) as partition_bound
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            LEFT JOIN pg_catalog.pg_tablespace t ON t.oid = c.reltablespace
+            WHERE c.oid = $1
+        "#;
+
+        let row = self.client.query_one(query, &[&oid]).await?;
+        let parent: Option<String> = row.get("parent");
+        let partition_of = parent.map(|name| PartitionInfo {
+            name,
+            bound: row
+                .get::<_, Option<String>>("partition_bound")
+                .unwrap_or_default(),
+        });
+
+        Ok(TableDetails {
+            schema: row.get("schema"),
+            name: row.get("name"),
+            kind: row.get("kind"),
+            owner: row.get("owner"),
+            tablespace: row.get("tablespace"),
+            comment: row.get("comment"),
+            row_estimate: row.get("row_estimate"),
+            heap_size: row.get("heap_size"),
+            toast_size: row.get("toast_size"),
+            indexes_size: row.get("indexes_size"),
+            total_size: row.get("total_size"),
+            row_level_security: row.get("row_level_security"),
+            force_row_level_security: row.get("force_row_level_security"),
+            partition_key: row.get("partition_key"),
+            partition_of,
+            columns: self.table_columns(oid).await?,
+            indexes: self.table_indexes(oid).await?,
+            constraints: self.table_constraints(oid).await?,
+            foreign_keys: self.foreign_keys(oid, false).await?,
+            referenced_by: self.foreign_keys(oid, true).await?,
+            triggers: self.table_triggers(oid).await?,
+            policies: self.table_policies(oid).await?,
+            partitions: self.table_partitions(oid).await?,
+        })
+    }
+
+    /// Looks up the OID of the relation `table` names, resolving unqualified
+    /// names through the session's `search_path`.
+    async fn relation_oid(&self, table: &QualifiedName) -> Result<u32, DconError> {
+        let row = self
+            .client
+            .query_one(
+                "SELECT pg_catalog.to_regclass($1)::pg_catalog.oid",
+                &[&table.quoted()],
+            )
+            .await?;
+
+        row.get::<_, Option<u32>>(0).ok_or_else(|| {
+            DconError::UserInput(format!("Did not find any relation named \"{table}\""))
+        })
+    }
+
+    async fn table_columns(&self, oid: u32) -> Result<Vec<ColumnInfo>, DconError> {
+        let query = r#"
+            SELECT
+                a.attname as column_name,
+                pg_catalog.format_type(a.atttypid, a.atttypmod) as data_type,
+                CASE WHEN a.attnotnull THEN 'NO' ELSE 'YES' END as is_nullable,
                 CASE
-                    WHEN pk.column_name IS NOT NULL THEN 'YES'
-                    ELSE 'NO'
-                END as is_primary
-            FROM information_schema.columns c
-            LEFT JOIN (
-                SELECT ku.column_name
-                FROM information_schema.table_constraints tc
-                JOIN information_schema.key_column_usage ku
-                    ON tc.constraint_name = ku.constraint_name
-                    AND tc.table_schema = ku.table_schema
-                    AND tc.table_name = ku.table_name
-                WHERE tc.constraint_type = 'PRIMARY KEY'
-                    AND tc.table_name = $1
-                    AND tc.table_schema = $2
-            ) pk ON c.column_name = pk.column_name
-            WHERE c.table_name = $1
-                AND c.table_schema = $2
-            ORDER BY c.ordinal_position;
+                    WHEN a.attidentity = 'a' THEN 'generated always as identity'
+                    WHEN a.attidentity = 'd' THEN 'generated by default as identity'
+                    WHEN a.attgenerated = 's' THEN
+                        'generated always as (' || pg_catalog.pg_get_expr(d.adbin, d.adrelid) || ') stored'
+                    ELSE COALESCE(pg_catalog.pg_get_expr(d.adbin, d.adrelid), 'NULL')
+                END as default_value,
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_index i
+                    WHERE i.indrelid = a.attrelid
+                        AND i.indisprimary
+                        AND a.attnum = ANY(i.indkey)
+                ) THEN 'YES' ELSE 'NO' END as is_primary,
+                CASE a.attstorage
+                    WHEN 'p' THEN 'plain'
+                    WHEN 'e' THEN 'external'
+                    WHEN 'm' THEN 'main'
+                    WHEN 'x' THEN 'extended'
+                    ELSE a.attstorage::text
+                END as storage,
+                pg_catalog.col_description(a.attrelid, a.attnum) as comment
+            FROM pg_catalog.pg_attribute a
+            LEFT JOIN pg_catalog.pg_attrdef d
+                ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = $1
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            ORDER BY a.attnum;
         "#;
 
-        let rows = self.client.query(query, &[&table_name, &schema]).await?;
+        let rows = self.client.query(query, &[&oid]).await?;
 
-        let columns: Vec<ColumnInfo> = rows
+        Ok(rows
             .into_iter()
             .map(|row| ColumnInfo {
                 column_name: row.get("column_name"),
@@ -226,39 +804,468 @@ impl PostgresClient {
                 is_nullable: row.get("is_nullable"),
                 default_value: row.get("default_value"),
                 is_primary: row.get("is_primary"),
+                storage: row.get("storage"),
+                comment: row.get("comment"),
             })
-            .collect();
+            .collect())
+    }
 
-        Ok(columns)
+    async fn table_indexes(&self, oid: u32) -> Result<Vec<IndexInfo>, DconError> {
+        let query = r#"
+            SELECT
+                c.relname as name,
+                pg_catalog.pg_get_indexdef(i.indexrelid, 0, true) as definition,
+                i.indisprimary as is_primary,
+                i.indisunique as is_unique,
+                i.indisvalid as is_valid,
+                pg_catalog.pg_size_pretty(pg_catalog.pg_relation_size(i.indexrelid)) as size
+            FROM pg_catalog.pg_index i
+            JOIN pg_catalog.pg_class c ON c.oid = i.indexrelid
+            WHERE i.indrelid = $1
+            ORDER BY i.indisprimary DESC, i.indisunique DESC, c.relname;
+        "#;
+
+        let rows = self.client.query(query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| IndexInfo {
+                name: row.get("name"),
+                definition: row.get("definition"),
+                is_primary: row.get("is_primary"),
+                is_unique: row.get("is_unique"),
+                is_valid: row.get("is_valid"),
+                size: row.get("size"),
+            })
+            .collect())
+    }
+
+    async fn table_constraints(&self, oid: u32) -> Result<Vec<ConstraintInfo>, DconError> {
+        let query = r#"
+            SELECT
+                conname as name,
+                CASE contype
+                    WHEN 'p' THEN 'primary key'
+                    WHEN 'u' THEN 'unique'
+                    WHEN 'c' THEN 'check'
+                    WHEN 'x' THEN 'exclusion'
+                END as constraint_type,
+                pg_catalog.pg_get_constraintdef(oid, true) as definition
+            FROM pg_catalog.pg_constraint
+            WHERE conrelid = $1
+                AND contype IN ('p', 'u', 'c', 'x')
+            ORDER BY contype = 'p' DESC, conname;
+        "#;
+
+        let rows = self.client.query(query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ConstraintInfo {
+                name: row.get("name"),
+                constraint_type: row.get("constraint_type"),
+                definition: row.get("definition"),
+            })
+            .collect())
+    }
+
+    /// Foreign keys defined on the table, or with `incoming` the foreign keys
+    /// of other tables that reference it.
+    async fn foreign_keys(
+        &self,
+        oid: u32,
+        incoming: bool,
+    ) -> Result<Vec<ForeignKeyInfo>, DconError> {
+        let query = format!(
+            r#"
+            SELECT
+                conname as name,
+                {}::pg_catalog.regclass::text as table_name,
+                pg_catalog.pg_get_constraintdef(oid, true) as definition
+            FROM pg_catalog.pg_constraint
+            WHERE {} = $1
+                AND contype = 'f'
+                AND conparentid = 0
+            ORDER BY table_name, conname;
+            "#,
+            if incoming { "conrelid" } else { "confrelid" },
+            if incoming { "confrelid" } else { "conrelid" },
+        );
+
+        let rows = self.client.query(&query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ForeignKeyInfo {
+                name: row.get("name"),
+                table: row.get("table_name"),
+                definition: row.get("definition"),
+            })
+            .collect())
+    }
+
+    async fn table_triggers(&self, oid: u32) -> Result<Vec<TriggerInfo>, DconError> {
+        let query = r#"
+            SELECT
+                tgname as name,
+                CASE tgenabled
+                    WHEN 'O' THEN 'enabled'
+                    WHEN 'D' THEN 'disabled'
+                    WHEN 'R' THEN 'replica only'
+                    WHEN 'A' THEN 'always'
+                END as enabled,
+                pg_catalog.pg_get_triggerdef(oid, true) as definition
+            FROM pg_catalog.pg_trigger
+            WHERE tgrelid = $1
+                AND NOT tgisinternal
+            ORDER BY tgname;
+        "#;
+
+        let rows = self.client.query(query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TriggerInfo {
+                name: row.get("name"),
+                enabled: row.get("enabled"),
+                definition: row.get("definition"),
+            })
+            .collect())
+    }
+
+    async fn table_policies(&self, oid: u32) -> Result<Vec<PolicyInfo>, DconError> {
+        let query = r#"
+            SELECT
+                p.polname as name,
+                CASE p.polcmd
+                    WHEN 'r' THEN 'SELECT'
+                    WHEN 'a' THEN 'INSERT'
+                    WHEN 'w' THEN 'UPDATE'
+                    WHEN 'd' THEN 'DELETE'
+                    ELSE 'ALL'
+                END as command,
+                p.polpermissive as permissive,
+                CASE WHEN p.polroles = '{0}' THEN 'public' ELSE
+                    pg_catalog.array_to_string(ARRAY(
+                        SELECT r.rolname FROM pg_catalog.pg_roles r
+                        WHERE r.oid = ANY(p.polroles)
+                        ORDER BY r.rolname
+                    ), ', ')
+                END as roles,
+                pg_catalog.pg_get_expr(p.polqual, p.polrelid) as using_expr,
+                pg_catalog.pg_get_expr(p.polwithcheck, p.polrelid) as with_check
+            FROM pg_catalog.pg_policy p
+            WHERE p.polrelid = $1
+            ORDER BY p.polname;
+        "#;
+
+        let rows = self.client.query(query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PolicyInfo {
+                name: row.get("name"),
+                command: row.get("command"),
+                permissive: row.get("permissive"),
+                roles: row.get("roles"),
+                using: row.get("using_expr"),
+                with_check: row.get("with_check"),
+            })
+            .collect())
     }
 
-    pub async fn execute_query(&self, query: &str) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
-        println!("{}", "🔧 Executing query...".cyan());
-        let rows = self
+    async fn table_partitions(&self, oid: u32) -> Result<Vec<PartitionInfo>, DconError> {
+        let query = r#"
+            SELECT
+                c.oid::pg_catalog.regclass::text as name,
+                COALESCE(pg_catalog.pg_get_expr(c.relpartbound, c.oid), '') as bound
+            FROM pg_catalog.pg_inherits i
+            JOIN pg_catalog.pg_class c ON c.oid = i.inhrelid
+            WHERE i.inhparent = $1
+            ORDER BY name;
+        "#;
+
+        let rows = self.client.query(query, &[&oid]).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PartitionInfo {
+                name: row.get("name"),
+                bound: row.get("bound"),
+            })
+            .collect())
+    }
+
+    /// Names of databases, schemas, relations (with their columns) and
+    /// functions, for tab completion. Objects in `pg_toast` and temporary
+    /// schemas of other sessions are left out.
+    pub async fn catalog_names(&self) -> Result<CatalogNames, DconError> {
+        let databases = self
+            .client
+            .query(
+                "SELECT datname FROM pg_database WHERE datallowconn ORDER BY datname",
+                &[],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let schemas = self
+            .client
+            .query(
+                r#"
+                SELECT nspname
+                FROM pg_namespace
+                WHERE nspname !~ '^pg_(toast|temp_|toast_temp_)' OR oid = pg_my_temp_schema()
+                ORDER BY nspname
+                "#,
+                &[],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        let relations = self
+            .client
+            .query(
+                r#"
+                SELECT
+                    n.nspname,
+                    c.relname,
+                    pg_table_is_visible(c.oid),
+                    coalesce(array_agg(a.attname::text ORDER BY a.attnum)
+                        FILTER (WHERE a.attname IS NOT NULL), '{}')
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                LEFT JOIN pg_attribute a
+                    ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped
+                WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+                  AND (n.nspname !~ '^pg_(toast|temp_|toast_temp_)' OR n.oid = pg_my_temp_schema())
+                GROUP BY c.oid, n.nspname, c.relname
+                ORDER BY n.nspname, c.relname
+                "#,
+                &[],
+            )
+            .await?
+            .iter()
+            .map(|row| CatalogObject {
+                schema: row.get(0),
+                name: row.get(1),
+                visible: row.get(2),
+                columns: row.get(3),
+            })
+            .collect();
+
+        let functions = self
             .client
-            .query(query, &[])
+            .query(
+                r#"
+                SELECT DISTINCT n.nspname, p.proname::text, pg_function_is_visible(p.oid)
+                FROM pg_proc p
+                JOIN pg_namespace n ON n.oid = p.pronamespace
+                WHERE p.prokind IN ('f', 'a', 'w')
+                ORDER BY 1, 2
+                "#,
+                &[],
+            )
+            .await?
+            .iter()
+            .map(|row| CatalogObject {
+                schema: row.get(0),
+                name: row.get(1),
+                visible: row.get(2),
+                columns: Vec::new(),
+            })
+            .collect();
+
+        Ok(CatalogNames {
+            databases,
+            schemas,
+            relations,
+            functions,
+        })
+    }
+
+    /// Runs one statement, binding `params` to its `$n` placeholders.
+    pub async fn execute_query(
+        &self,
+        query: &str,
+        params: &QueryParams,
+    ) -> Result<QueryResult, DconError> {
+        let mut rows = Vec::new();
+        let mut result = self
+            .stream_query(query, params, 0, |_, batch| {
+                rows = batch;
+                Ok(())
+            })
+            .await?;
+        result.rows = rows;
+
+        Ok(result)
+    }
+
+    /// Runs one statement like [`execute_query`](Self::execute_query), but
+    /// hands its rows to `on_rows` as they arrive, `fetch_count` at a time,
+    /// instead of collecting them. With a `fetch_count` of 0 all rows come in
+    /// one batch at the end. `on_rows` is never called without rows.
+    ///
+    /// Rows are read from the connection as they are consumed, so no more
+    /// than a batch is held in memory however large the result.
+    pub async fn stream_query<F>(
+        &self,
+        query: &str,
+        params: &QueryParams,
+        fetch_count: usize,
+        on_rows: F,
+    ) -> Result<QueryResult, DconError>
+    where
+        F: FnMut(&[Column], Vec<Row>) -> Result<(), DconError>,
+    {
+        status!("{}", "🔧 Executing query...".cyan());
+        self.canceller()
+            .run(self.read_rows(query, params, fetch_count, on_rows))
             .await
-            .map_err(|e| format!("Query execution failed: {e}"))
-            .unwrap();
+    }
 
-        Ok(rows)
+    /// Runs `query` again under `EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON)`
+    /// for the server's planning and execution times and buffer usage. Like
+    /// any `EXPLAIN ANALYZE`, this executes the statement.
+    pub async fn explain_analyze(
+        &self,
+        query: &str,
+        params: &QueryParams,
+    ) -> Result<ServerStats, DconError> {
+        let explain = format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {query}");
+        let mut plan = Value::Null;
+        self.canceller()
+            .run(self.read_rows(&explain, params, 0, |_, rows| {
+                if let Some(row) = rows.first() {
+                    plan = column_value(row, 0).to_json();
+                }
+                Ok(())
+            }))
+            .await?;
+
+        Ok(ServerStats::from_explain(&plan))
     }
 
-    pub async fn insert_data(
+    async fn read_rows<F>(
         &self,
-        table_name: &str,
-        data: &Value,
-    ) -> Result<u64, Box<dyn std::error::Error>> {
-        if !data.is_object() {
-            return Err("Data must be a JSON object".into());
+        query: &str,
+        params: &QueryParams,
+        fetch_count: usize,
+        mut on_rows: F,
+    ) -> Result<QueryResult, DconError>
+    where
+        F: FnMut(&[Column], Vec<Row>) -> Result<(), DconError>,
+    {
+        let started = Instant::now();
+        let sql_error = |e| DconError::from(e).with_statement(query);
+        let types = self.resolve_types(&params.types).await?;
+        let statement = self
+            .client
+            .prepare_typed(query, &types)
+            .await
+            .map_err(sql_error)?;
+
+        if statement.params().len() != params.values.len() {
+            return Err(DconError::UserInput(format!(
+                "The statement has {} parameter(s) but {} value(s) were given",
+                statement.params().len(),
+                params.values.len()
+            )));
+        }
+        // The rows may come from a form of the statement that reads some
+        // columns as text; callers see the columns as the statement has them.
+        let (source, offset) = match text_query(query, statement.columns()) {
+            Some((wrapped, offset)) => {
+                let source = self
+                    .client
+                    .prepare_typed(&wrapped, &types)
+                    .await
+                    .map_err(|e| DconError::from(e).with_statement_at(query, offset))?;
+                (source, offset)
+            }
+            None => (statement.clone(), 0),
+        };
+        let sql_error = |e| DconError::from(e).with_statement_at(query, offset);
+
+        let stream = self
+            .client
+            .query_raw(&source, params.values.iter().map(|value| TextParam(value)))
+            .await
+            .map_err(sql_error)?;
+        tokio::pin!(stream);
+
+        let mut timing = QueryTiming::default();
+        let mut batch = Vec::new();
+        while let Some(row) = stream.try_next().await.map_err(sql_error)? {
+            timing.first_row.get_or_insert_with(|| started.elapsed());
+            timing.rows += 1;
+            timing.bytes += row_size(&row) as u64;
+            batch.push(row);
+            if batch.len() == fetch_count {
+                on_rows(statement.columns(), std::mem::take(&mut batch))?;
+            }
+        }
+        timing.elapsed = started.elapsed();
+        if !batch.is_empty() {
+            on_rows(statement.columns(), batch)?;
         }
 
-        let obj = data.as_object().unwrap();
+        Ok(QueryResult {
+            rows_affected: stream.rows_affected(),
+            statement,
+            rows: Vec::new(),
+            timing,
+        })
+    }
+
+    /// Looks up types named on the command line, such as `int8` or
+    /// `timestamp with time zone`.
+    async fn resolve_types(&self, names: &[String]) -> Result<Vec<Type>, DconError> {
+        let mut types = Vec::with_capacity(names.len());
+        for name in names {
+            let row = self
+                .client
+                .query_one("SELECT to_regtype($1)::oid", &[name])
+                .await?;
+            let Some(oid) = row.get::<_, Option<u32>>(0) else {
+                return Err(DconError::UserInput(format!("Unknown type: {name}")));
+            };
+            // Values are sent as text, so the kind of a type the driver does
+            // not know (an enum or a domain, say) does not matter.
+            types.push(
+                Type::from_oid(oid)
+                    .unwrap_or_else(|| Type::new(name.clone(), oid, Kind::Simple, String::new())),
+            );
+        }
+
+        Ok(types)
+    }
+
+    /// Runs statements that return no rows, such as transaction control,
+    /// without a status message.
+    pub async fn batch_execute(&self, sql: &str) -> Result<(), DconError> {
+        self.client
+            .batch_execute(sql)
+            .await
+            .map_err(|e| DconError::from(e).with_statement(sql))
+    }
+
+    pub async fn insert_data(&self, table: &QualifiedName, data: &Value) -> Result<u64, DconError> {
+        let Some(obj) = data.as_object() else {
+            return Err(DconError::UserInput(
+                "Data must be a JSON object".to_string(),
+            ));
+        };
         let columns: Vec<&String> = obj.keys().collect();
         let values: Vec<&Value> = obj.values().collect();
 
         if columns.is_empty() {
-            return Err("No data provided".into());
+            return Err(DconError::UserInput("No data provided".to_string()));
         }
 
         let column_list = columns
@@ -267,125 +1274,132 @@ impl PostgresClient {
             .collect::<Vec<_>>()
             .join(", ");
 
-        let placeholders = (1..=values.len())
-            .map(|i| format!("${i}"))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let query = format!(
-            "INSERT INTO \"{}\" ({}) VALUES ({})",
-            table_name.replace("\"", "\"\""),
-            column_list,
-            placeholders
-        );
-
-        // Convert JSON values to strings for postgres compatibility
-        let string_values: Vec<String> = values
-            .iter()
-            .map(|v| match v {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Null => "NULL".to_string(),
-                _ => v.to_string().trim_matches('"').to_string(),
+        let (query, statement, params) = self
+            .prepare_json_params(&values, |placeholders| {
+                format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table.quoted(),
+                    column_list,
+                    placeholders.join(", ")
+                )
             })
-            .collect();
+            .await?;
 
-        let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = string_values
-            .iter()
-            .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
-            .collect();
-
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         let result = self
             .client
-            .execute(&query, &params)
+            .execute_raw(&statement, &params)
             .await
-            .map_err(|e| format!("Insert failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(result)
     }
 
     pub async fn update_data(
         &self,
-        table_name: &str,
+        table: &QualifiedName,
         set_data: &Value,
         where_clause: &str,
-    ) -> Result<u64, Box<dyn std::error::Error>> {
-        if !set_data.is_object() {
-            return Err("Set data must be a JSON object".into());
-        }
-
-        let obj = set_data.as_object().unwrap();
-        if obj.is_empty() {
-            return Err("No update data provided".into());
-        }
-
-        let mut set_clauses = Vec::new();
-        let mut string_values = Vec::new();
-        let mut param_index = 1;
-
-        for (key, value) in obj.iter() {
-            set_clauses.push(format!(
-                "\"{}\" = ${}",
-                key.replace("\"", "\"\""),
-                param_index
+    ) -> Result<u64, DconError> {
+        let Some(obj) = set_data.as_object() else {
+            return Err(DconError::UserInput(
+                "Set data must be a JSON object".to_string(),
             ));
-
-            let string_value = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Null => "NULL".to_string(),
-                _ => value.to_string().trim_matches('"').to_string(),
-            };
-
-            string_values.push(string_value);
-            param_index += 1;
+        };
+        if obj.is_empty() {
+            return Err(DconError::UserInput("No update data provided".to_string()));
         }
 
-        let params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = string_values
-            .iter()
-            .map(|s| s as &(dyn tokio_postgres::types::ToSql + Sync))
-            .collect();
+        let columns: Vec<&String> = obj.keys().collect();
+        let values: Vec<&Value> = obj.values().collect();
 
-        let query = format!(
-            "UPDATE \"{}\" SET {} WHERE {}",
-            table_name.replace("\"", "\"\""),
-            set_clauses.join(", "),
-            where_clause
-        );
+        let (query, statement, params) = self
+            .prepare_json_params(&values, |placeholders| {
+                let set_clauses = columns
+                    .iter()
+                    .zip(placeholders)
+                    .map(|(column, placeholder)| {
+                        format!("\"{}\" = {}", column.replace("\"", "\"\""), placeholder)
+                    })
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "UPDATE {} SET {} WHERE {}",
+                    table.quoted(),
+                    set_clauses.join(", "),
+                    where_clause
+                )
+            })
+            .await?;
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         let result = self
             .client
-            .execute(&query, &params)
+            .execute_raw(&statement, &params)
             .await
-            .map_err(|e| format!("Update failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(result)
     }
 
+    /// Prepares the statement built by `build` from one placeholder per value,
+    /// with each value encoded for the type the server infers for its
+    /// parameter (the type of the column it is assigned to). Placeholders of
+    /// values that need a server-side conversion are cast from text, which
+    /// takes a second prepare.
+    async fn prepare_json_params(
+        &self,
+        values: &[&Value],
+        build: impl Fn(&[String]) -> String,
+    ) -> Result<(String, Statement, Vec<JsonParam>), DconError> {
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("${i}")).collect();
+        let mut query = build(&placeholders);
+        let mut statement = self
+            .client
+            .prepare(&query)
+            .await
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
+
+        let types = statement.params().to_vec();
+        let typed_placeholders: Vec<String> = (1..)
+            .zip(values.iter().zip(&types))
+            .map(|(index, (value, ty))| placeholder(index, value, ty))
+            .collect();
+
+        if typed_placeholders != placeholders {
+            query = build(&typed_placeholders);
+            statement = self
+                .client
+                .prepare(&query)
+                .await
+                .map_err(|e| DconError::from(e).with_statement(&query))?;
+        }
+
+        let params = values
+            .iter()
+            .zip(&types)
+            .map(|(value, ty)| JsonParam::new(value, ty))
+            .collect();
+
+        Ok((query, statement, params))
+    }
+
     pub async fn delete_data(
         &self,
-        table_name: &str,
+        table: &QualifiedName,
         where_clause: &str,
-    ) -> Result<u64, Box<dyn std::error::Error>> {
-        let query = format!(
-            "DELETE FROM \"{}\" WHERE {}",
-            table_name.replace("\"", "\"\""),
-            where_clause
-        );
+    ) -> Result<u64, DconError> {
+        let query = format!("DELETE FROM {} WHERE {}", table.quoted(), where_clause);
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         let result = self
             .client
             .execute(&query, &[])
             .await
-            .map_err(|e| format!("Delete failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(result)
     }
@@ -395,7 +1409,7 @@ impl PostgresClient {
         name: &str,
         owner: Option<&str>,
         encoding: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), DconError> {
         let mut query = format!("CREATE DATABASE \"{}\"", name.replace("\"", "\"\""));
 
         if let Some(owner) = owner {
@@ -404,57 +1418,61 @@ impl PostgresClient {
 
         query.push_str(&format!(" ENCODING '{}'", encoding.replace("'", "''")));
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         self.client
             .execute(&query, &[])
             .await
-            .map_err(|e| format!("Create database failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(())
     }
 
-    pub async fn drop_database(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn drop_database(&self, name: &str) -> Result<(), DconError> {
         let query = format!("DROP DATABASE \"{}\"", name.replace("\"", "\"\""));
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         self.client
             .execute(&query, &[])
             .await
-            .map_err(|e| format!("Drop database failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(())
     }
 
-    pub async fn drop_table(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let query = format!("DROP TABLE \"{}\"", name.replace("\"", "\"\""));
+    pub async fn drop_table(&self, table: &QualifiedName) -> Result<(), DconError> {
+        let query = format!("DROP TABLE {}", table.quoted());
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
         self.client
             .execute(&query, &[])
             .await
-            .map_err(|e| format!("Drop table failed: {e}"))?;
+            .map_err(|e| DconError::from(e).with_statement(&query))?;
 
         Ok(())
     }
 
-    pub async fn select_data(
+    /// Selects rows from `table`, handing them to `on_rows` as
+    /// [`stream_query`](Self::stream_query) does.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn select_data<F>(
         &self,
-        table_name: &str,
+        table: &QualifiedName,
         columns: Option<&str>,
         where_clause: Option<&str>,
         order_by: Option<&str>,
         limit: Option<i64>,
         offset: Option<i64>,
-    ) -> Result<Vec<Row>, Box<dyn std::error::Error>> {
+        fetch_count: usize,
+        on_rows: F,
+    ) -> Result<QueryResult, DconError>
+    where
+        F: FnMut(&[Column], Vec<Row>) -> Result<(), DconError>,
+    {
         let column_list = columns.unwrap_or("*");
-        let mut query = format!(
-            "SELECT {} FROM \"{}\"",
-            column_list,
-            table_name.replace("\"", "\"\"")
-        );
+        let mut query = format!("SELECT {} FROM {}", column_list, table.quoted());
 
         if let Some(where_clause) = where_clause {
             query.push_str(&format!(" WHERE {where_clause}"));
@@ -472,66 +1490,87 @@ impl PostgresClient {
             query.push_str(&format!(" OFFSET {offset}"));
         }
 
-        println!("{}", format!("📝 Executing: {query}").dimmed());
+        status!("{}", format!("📝 Executing: {query}").dimmed());
 
-        let rows = self
-            .client
-            .query(&query, &[])
+        self.stream_query(&query, &QueryParams::default(), fetch_count, on_rows)
             .await
-            .map_err(|e| format!("Select failed: {e}"))?;
-
-        Ok(rows)
     }
 
-    pub async fn get_table_row_count(
-        &self,
-        table_name: &str,
-        schema: Option<&str>,
-    ) -> Result<i64, Box<dyn std::error::Error>> {
-        let schema = schema.unwrap_or("public");
-        let query = format!(
-            "SELECT COUNT(*) FROM \"{}\".\"{}\"",
-            schema.replace("\"", "\"\""),
-            table_name.replace("\"", "\"\"")
-        );
+    pub async fn get_table_row_count(&self, table: &QualifiedName) -> Result<i64, DconError> {
+        let query = format!("SELECT COUNT(*) FROM {}", table.quoted());
 
-        let row = self
-            .client
-            .query_one(&query, &[])
-            .await
-            .map_err(|e| format!("Failed to get row count: {e}"))?;
+        let row = self.client.query_one(&query, &[]).await?;
 
         let count: i64 = row.get(0);
         Ok(count)
     }
+}
 
-    pub fn get_column_value(row: &Row, index: usize) -> String {
-        // Try to get the value as different types and convert to string
-        if let Ok(val) = row.try_get::<_, Option<String>>(index) {
-            val.unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<i64>>(index) {
-            val.map(|v| v.to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<i32>>(index) {
-            val.map(|v| v.to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<f64>>(index) {
-            val.map(|v| v.to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<f32>>(index) {
-            val.map(|v| v.to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<bool>>(index) {
-            val.map(|v| v.to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<NaiveDateTime>>(index) {
-            val.map(|v| v.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else if let Ok(val) = row.try_get::<_, Option<NaiveDate>>(index) {
-            val.map(|v| v.format("%Y-%m-%d").to_string())
-                .unwrap_or_else(|| "NULL".to_string())
-        } else {
-            "Unknown Type".to_string()
-        }
+/// `query` wrapped so that the columns whose values [`needs_text`] come back
+/// as the server's text, with the number of characters before `query` in
+/// it, or `None` if there are no such columns. Only queries that just read
+/// are wrapped; statements that write are read as they are. A query with
+/// `ORDER BY` is not merged into the outer one, whose plain scan keeps its
+/// order.
+fn text_query(query: &str, columns: &[Column]) -> Option<(String, usize)> {
+    if !columns.iter().any(|column| needs_text(column.type_())) || !is_read_only(query) {
+        return None;
     }
+
+    let names: Vec<String> = (1..=columns.len()).map(|i| format!("\"{i}\"")).collect();
+    let select: Vec<String> = columns
+        .iter()
+        .zip(&names)
+        .map(|(column, name)| {
+            if needs_text(column.type_()) {
+                format!("{name}::text")
+            } else {
+                name.clone()
+            }
+        })
+        .collect();
+
+    let prefix = format!("WITH dcon_result ({}) AS (\n", names.join(", "));
+    // The newline ends a trailing `--` comment.
+    let wrapped = format!(
+        "{prefix}{query}\n) SELECT {} FROM dcon_result",
+        select.join(", ")
+    );
+    Some((wrapped, prefix.chars().count()))
+}
+
+/// Drives the connection in the background until the client is dropped.
+fn spawn_connection<S, T>(connection: Connection<S, T>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection Error: {e}");
+        }
+    });
+}
+
+/// Condition restricting objects to a `\d`-style pattern, bound as `$1`
+/// (schema) and `$2` (name): without a pattern, the objects visible on the
+/// search_path outside the system schemas; with an unqualified pattern,
+/// visible objects whose name matches; with a qualified one, matching objects
+/// in matching schemas.
+fn pattern_condition(schema: &str, name: &str, visible: &str) -> String {
+    format!(
+        r#"CASE
+                WHEN $2::text IS NULL THEN {schema} NOT IN ('pg_catalog', 'information_schema')
+                    AND {schema} !~ '^pg_toast' AND {visible}
+                WHEN $1::text IS NULL THEN {name} ~ $2 AND {visible}
+                ELSE {schema} ~ $1 AND {name} ~ $2
+            END"#
+    )
+}
+
+fn pattern_params(pattern: Option<&NamePattern>) -> (Option<&str>, Option<&str>) {
+    (
+        pattern.and_then(|pattern| pattern.schema.as_deref()),
+        pattern.map(|pattern| pattern.name.as_str()),
+    )
 }
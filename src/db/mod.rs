@@ -1 +1,4 @@
 pub mod client;
+pub mod params;
+pub mod tls;
+pub mod value;
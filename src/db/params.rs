@@ -0,0 +1,374 @@
+use bytes::BytesMut;
+use serde_json::Value;
+use std::error::Error;
+use tokio_postgres::types::{to_sql_checked, Format, IsNull, Kind, ToSql, Type};
+
+/// A JSON value encoded for a parameter of a known type.
+///
+/// Values that map directly onto the parameter type (numbers into integer
+/// columns, objects into `jsonb`, arrays into array columns, ...) are sent in
+/// binary form. Anything else is sent as text and converted by the server,
+/// which needs the placeholder written with [`placeholder`].
+#[derive(Debug)]
+pub struct JsonParam(Option<Vec<u8>>);
+
+impl JsonParam {
+    /// Encodes `value` for a parameter whose inferred type is `ty`.
+    pub fn new(value: &Value, ty: &Type) -> Self {
+        if binds_natively(value, ty) {
+            JsonParam(encode(value, ty))
+        } else {
+            JsonParam(Some(text_literal(value, ty).into_bytes()))
+        }
+    }
+}
+
+impl ToSql for JsonParam {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match &self.0 {
+            Some(bytes) => {
+                out.extend_from_slice(bytes);
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// Values for the `$n` placeholders of a statement, as given with `--param`
+/// and `--param-type` or taken from variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    /// Values in text form, for `$1`, `$2`, ...
+    pub values: Vec<String>,
+    /// Type names for the first placeholders; the server infers the rest
+    pub types: Vec<String>,
+}
+
+/// A value sent in text form, which the server parses as the parameter's
+/// type just as it would a literal.
+#[derive(Debug)]
+pub struct TextParam<'a>(pub &'a str);
+
+impl ToSql for TextParam<'_> {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        out.extend_from_slice(self.0.as_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
+
+/// The placeholder for parameter `index` (1-based) holding `value` for a
+/// parameter of type `ty`: `$n` when the value binds natively, otherwise an
+/// explicit cast from text to the parameter type.
+pub fn placeholder(index: usize, value: &Value, ty: &Type) -> String {
+    if binds_natively(value, ty) {
+        format!("${index}")
+    } else {
+        format!(
+            "CAST(${index}::text AS \"{}\".\"{}\")",
+            ty.schema().replace('"', "\"\""),
+            ty.name().replace('"', "\"\"")
+        )
+    }
+}
+
+/// Whether `value` can be sent in the binary format of `ty`.
+fn binds_natively(value: &Value, ty: &Type) -> bool {
+    match (value, ty.kind()) {
+        (Value::Null, _) => true,
+        (_, Kind::Domain(inner)) => binds_natively(value, inner),
+        (_, _) if is_json(ty) => true,
+        (Value::String(_), Kind::Enum(_)) => true,
+        (Value::Array(_), Kind::Array(member)) => {
+            array_shape(value).is_some() && leaves(value).all(|leaf| binds_natively(leaf, member))
+        }
+        (Value::Bool(_), _) => *ty == Type::BOOL,
+        (Value::String(_), _) => is_text(ty),
+        (Value::Number(n), _) => match *ty {
+            Type::INT2 => n.as_i64().is_some_and(|i| i16::try_from(i).is_ok()),
+            Type::INT4 => n.as_i64().is_some_and(|i| i32::try_from(i).is_ok()),
+            Type::INT8 => n.as_i64().is_some(),
+            Type::OID => n.as_u64().is_some_and(|i| u32::try_from(i).is_ok()),
+            Type::FLOAT4 | Type::FLOAT8 => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_json(ty: &Type) -> bool {
+    *ty == Type::JSON || *ty == Type::JSONB
+}
+
+fn is_text(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN
+    )
+}
+
+/// Binary encoding of a value accepted by [`binds_natively`].
+fn encode(value: &Value, ty: &Type) -> Option<Vec<u8>> {
+    if let Kind::Domain(inner) = ty.kind() {
+        return encode(value, inner);
+    }
+
+    let bytes = match value {
+        Value::Null => return None,
+        _ if *ty == Type::JSON => value.to_string().into_bytes(),
+        // jsonb's binary format is a version byte followed by the JSON text.
+        _ if *ty == Type::JSONB => [&[1], value.to_string().as_bytes()].concat(),
+        Value::Bool(b) => vec![u8::from(*b)],
+        Value::String(s) => s.as_bytes().to_vec(),
+        Value::Number(n) => match *ty {
+            Type::INT2 => (n.as_i64()? as i16).to_be_bytes().to_vec(),
+            Type::INT4 => (n.as_i64()? as i32).to_be_bytes().to_vec(),
+            Type::INT8 => n.as_i64()?.to_be_bytes().to_vec(),
+            Type::OID => (n.as_u64()? as u32).to_be_bytes().to_vec(),
+            Type::FLOAT4 => (n.as_f64()? as f32).to_be_bytes().to_vec(),
+            _ => n.as_f64()?.to_be_bytes().to_vec(),
+        },
+        Value::Array(_) => match ty.kind() {
+            Kind::Array(member) => encode_array(value, member),
+            _ => return None,
+        },
+        Value::Object(_) => return None,
+    };
+
+    Some(bytes)
+}
+
+fn encode_array(value: &Value, member: &Type) -> Vec<u8> {
+    let shape = array_shape(value).unwrap_or_default();
+    let elements: Vec<Option<Vec<u8>>> = leaves(value).map(|leaf| encode(leaf, member)).collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&(shape.len() as i32).to_be_bytes());
+    out.extend_from_slice(&i32::from(elements.iter().any(Option::is_none)).to_be_bytes());
+    out.extend_from_slice(&member.oid().to_be_bytes());
+    for len in &shape {
+        out.extend_from_slice(&(*len as i32).to_be_bytes());
+        // Lower bound
+        out.extend_from_slice(&1_i32.to_be_bytes());
+    }
+    for element in elements {
+        match element {
+            Some(bytes) => {
+                out.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+                out.extend_from_slice(&bytes);
+            }
+            None => out.extend_from_slice(&(-1_i32).to_be_bytes()),
+        }
+    }
+
+    out
+}
+
+/// Dimensions of a (possibly nested) JSON array, or `None` when the nesting
+/// is ragged and so cannot form a PostgreSQL array. Empty arrays have no
+/// dimensions.
+fn array_shape(value: &Value) -> Option<Vec<usize>> {
+    let Value::Array(items) = value else {
+        return Some(Vec::new());
+    };
+    let Some(first) = items.first() else {
+        return Some(Vec::new());
+    };
+
+    let inner = array_shape(first)?;
+    for item in &items[1..] {
+        if item.is_array() != first.is_array() || array_shape(item)? != inner {
+            return None;
+        }
+    }
+
+    Some([vec![items.len()], inner].concat())
+}
+
+/// The scalar elements of a nested JSON array in row-major order.
+fn leaves(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Array(items) => Box::new(items.iter().flat_map(leaves)),
+        leaf => Box::new(std::iter::once(leaf)),
+    }
+}
+
+/// Text input for `value` that the server converts to `ty`. JSON arrays
+/// bound to array types become array literals such as `{1,"a b",NULL}`.
+fn text_literal(value: &Value, ty: &Type) -> String {
+    match (value, ty.kind()) {
+        (_, Kind::Domain(inner)) => text_literal(value, inner),
+        (Value::Array(items), Kind::Array(_)) => array_literal(items),
+        (Value::String(s), _) => s.clone(),
+        (value, _) => value.to_string(),
+    }
+}
+
+fn array_literal(items: &[Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::Null => "NULL".to_string(),
+            Value::Array(inner) => array_literal(inner),
+            Value::String(s) => quote_element(s),
+            other => quote_element(&other.to_string()),
+        })
+        .collect();
+
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_element(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::value::decode;
+    use serde_json::json;
+
+    fn bytes(value: Value, ty: &Type) -> Option<Vec<u8>> {
+        JsonParam::new(&value, ty).0
+    }
+
+    #[test]
+    fn scalars_bind_in_binary() {
+        let cases = [
+            (json!(7), Type::INT2, vec![0, 7]),
+            (json!(-2), Type::INT4, (-2_i32).to_be_bytes().to_vec()),
+            (
+                json!(1_i64 << 40),
+                Type::INT8,
+                (1_i64 << 40).to_be_bytes().to_vec(),
+            ),
+            (json!(42), Type::OID, 42_u32.to_be_bytes().to_vec()),
+            (json!(0.5), Type::FLOAT4, 0.5_f32.to_be_bytes().to_vec()),
+            (json!(3), Type::FLOAT8, 3.0_f64.to_be_bytes().to_vec()),
+            (json!(true), Type::BOOL, vec![1]),
+            (json!("héllo"), Type::TEXT, "héllo".as_bytes().to_vec()),
+        ];
+        for (value, ty, expected) in cases {
+            assert_eq!(placeholder(1, &value, &ty), "$1", "{ty}");
+            assert_eq!(bytes(value, &ty), Some(expected), "{ty}");
+        }
+    }
+
+    #[test]
+    fn json_documents() {
+        let value = json!({"a": [1, null]});
+        assert_eq!(
+            bytes(value.clone(), &Type::JSON),
+            Some(br#"{"a":[1,null]}"#.to_vec())
+        );
+        // jsonb's binary format starts with a version byte.
+        assert_eq!(
+            bytes(value, &Type::JSONB),
+            Some(b"\x01{\"a\":[1,null]}".to_vec())
+        );
+        assert_eq!(bytes(json!("x"), &Type::JSONB), Some(b"\x01\"x\"".to_vec()));
+    }
+
+    #[test]
+    fn null_is_sent_as_null() {
+        let param = JsonParam::new(&Value::Null, &Type::INT4);
+        assert_eq!(placeholder(1, &Value::Null, &Type::INT4), "$1");
+        assert!(matches!(
+            param.to_sql(&Type::INT4, &mut BytesMut::new()),
+            Ok(IsNull::Yes)
+        ));
+    }
+
+    #[test]
+    fn arrays_bind_in_binary() {
+        let cases = [
+            (json!([1, null, 3]), Type::INT4_ARRAY, "{1,NULL,3}"),
+            (json!([[1, 2], [3, 4]]), Type::INT8_ARRAY, "{{1,2},{3,4}}"),
+            (json!([]), Type::INT4_ARRAY, "{}"),
+            (
+                json!(["a b", "q\"t"]),
+                Type::TEXT_ARRAY,
+                r#"{"a b","q\"t"}"#,
+            ),
+        ];
+        for (value, ty, expected) in cases {
+            assert_eq!(placeholder(1, &value, &ty), "$1", "{value}");
+            let raw = bytes(value, &ty).expect("not NULL");
+            assert_eq!(decode(&ty, &raw).expect("decodable").to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn other_values_are_cast_from_text() {
+        let cases = [
+            (json!("12.50"), Type::NUMERIC, "12.50", "numeric"),
+            (json!(12.5), Type::NUMERIC, "12.5", "numeric"),
+            (
+                json!("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"),
+                Type::UUID,
+                "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+                "uuid",
+            ),
+            (
+                json!("2024-06-01 12:00:00+02"),
+                Type::TIMESTAMPTZ,
+                "2024-06-01 12:00:00+02",
+                "timestamptz",
+            ),
+            // Arrays that do not bind natively become array literals.
+            (
+                json!([[1, 2], [3]]),
+                Type::INT4_ARRAY,
+                r#"{{"1","2"},{"3"}}"#,
+                "_int4",
+            ),
+            (
+                json!(["x", null]),
+                Type::DATE_ARRAY,
+                r#"{"x",NULL}"#,
+                "_date",
+            ),
+        ];
+        for (value, ty, text, name) in cases {
+            assert_eq!(
+                placeholder(2, &value, &ty),
+                format!("CAST($2::text AS \"pg_catalog\".\"{name}\")")
+            );
+            assert_eq!(bytes(value, &ty), Some(text.as_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn mismatched_values_are_left_to_the_server() {
+        // Values that do not fit the column are sent as text, so that the
+        // server rejects them with its own message.
+        let cases = [
+            (json!(70000), Type::INT2, "70000"),
+            (json!(1.5), Type::INT4, "1.5"),
+            (json!("abc"), Type::INT4, "abc"),
+            (json!(true), Type::INT4, "true"),
+            (json!(1), Type::BOOL, "1"),
+            (json!({"a": 1}), Type::TEXT, r#"{"a":1}"#),
+        ];
+        for (value, ty, text) in cases {
+            assert!(placeholder(1, &value, &ty).starts_with("CAST($1::text"));
+            assert_eq!(bytes(value, &ty), Some(text.as_bytes().to_vec()));
+        }
+    }
+}
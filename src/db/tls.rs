@@ -0,0 +1,73 @@
+use crate::error::DconError;
+use crate::models::connection::ConnectionConfig;
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use std::fs;
+
+/// Builds the TLS connector for a connection according to its `sslmode`.
+///
+/// Like libpq, `prefer` and `require` only encrypt the session unless a root
+/// certificate is supplied, in which case the chain is verified as with
+/// `verify-ca`. `verify-full` additionally checks the host name.
+pub fn make_tls_connector(config: &ConnectionConfig) -> Result<MakeTlsConnector, DconError> {
+    let mut builder = TlsConnector::builder();
+
+    let verify_chain = config.sslmode.verifies_certificate() || config.sslrootcert.is_some();
+
+    if let Some(path) = &config.sslrootcert {
+        let pem = fs::read(path).map_err(|e| {
+            DconError::UserInput(format!("Failed to read sslrootcert '{path}': {e}"))
+        })?;
+        let certs = Certificate::stack_from_pem(&pem).map_err(|e| {
+            DconError::UserInput(format!("Invalid certificate in sslrootcert '{path}': {e}"))
+        })?;
+        if certs.is_empty() {
+            return Err(DconError::UserInput(format!(
+                "No certificates found in sslrootcert '{path}'"
+            )));
+        }
+
+        // An explicit root file replaces the system trust store, as in libpq.
+        builder.disable_built_in_roots(true);
+        for cert in certs {
+            builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&config.sslcert, &config.sslkey) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = fs::read(cert_path).map_err(|e| {
+                DconError::UserInput(format!("Failed to read sslcert '{cert_path}': {e}"))
+            })?;
+            let key = fs::read(key_path).map_err(|e| {
+                DconError::UserInput(format!("Failed to read sslkey '{key_path}': {e}"))
+            })?;
+            let identity = Identity::from_pkcs8(&cert, &key).map_err(|e| {
+                DconError::UserInput(format!(
+                    "Invalid client certificate or key (PKCS#8 PEM expected): {e}"
+                ))
+            })?;
+            builder.identity(identity);
+        }
+        (Some(_), None) => {
+            return Err(DconError::UserInput(
+                "sslcert requires sslkey to be set".to_string(),
+            ))
+        }
+        (None, Some(_)) => {
+            return Err(DconError::UserInput(
+                "sslkey requires sslcert to be set".to_string(),
+            ))
+        }
+        (None, None) => {}
+    }
+
+    builder.danger_accept_invalid_certs(!verify_chain);
+    builder.danger_accept_invalid_hostnames(!config.sslmode.verifies_hostname());
+
+    let connector = builder
+        .build()
+        .map_err(|e| DconError::Connection(format!("Failed to initialise TLS: {e}")))?;
+
+    Ok(MakeTlsConnector::new(connector))
+}
@@ -0,0 +1,1207 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use serde_json::{Map, Number, Value};
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+use tokio_postgres::types::{FromSql, Kind, Type};
+use tokio_postgres::Row;
+
+type DecodeError = Box<dyn Error + Sync + Send>;
+
+/// A decoded column value.
+///
+/// Values arrive in PostgreSQL's binary format. Every built-in type is decoded
+/// here; enums, domains, arrays, ranges and composites are decoded through
+/// their underlying types, and unrecognised extension types fall back to
+/// their bytes as text (or `\x` hex when they are not valid UTF-8).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// A `real`, kept single-precision so that it prints as the server does
+    Float4(f32),
+    /// Exact decimal text of a `numeric` or `money` value (or `NaN`/`Infinity`)
+    Numeric(String),
+    Text(String),
+    /// A date/time value: psql's text form and its ISO 8601 form for JSON
+    DateTime {
+        text: String,
+        iso: String,
+    },
+    Json(Value),
+    Array(Vec<PgValue>),
+    Composite(Vec<(String, PgValue)>),
+}
+
+impl PgValue {
+    /// Converts the value to JSON, keeping numbers, booleans, nulls, nested
+    /// JSON documents and arrays typed.
+    pub fn to_json(&self) -> Value {
+        match self {
+            PgValue::Null => Value::Null,
+            PgValue::Bool(b) => Value::Bool(*b),
+            PgValue::Int(i) => Value::from(*i),
+            PgValue::Float(f) => float_json(*f),
+            // Widened through its shortest text, so that 0.1 stays 0.1.
+            PgValue::Float4(f) => float_json(f.to_string().parse().unwrap_or(f64::NAN)),
+            PgValue::Numeric(n) => numeric_to_json(n),
+            PgValue::Text(s) => Value::String(s.clone()),
+            PgValue::DateTime { iso, .. } => Value::String(iso.clone()),
+            PgValue::Json(v) => v.clone(),
+            PgValue::Array(items) => Value::Array(items.iter().map(PgValue::to_json).collect()),
+            PgValue::Composite(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+
+    /// Text of the value when nested inside an array or composite literal.
+    fn literal_element(&self, quote_chars: &[char]) -> String {
+        let text = match self {
+            PgValue::Null => return String::new(),
+            other => other.to_string(),
+        };
+
+        let needs_quotes = text.is_empty()
+            || text.eq_ignore_ascii_case("null")
+            || text
+                .chars()
+                .any(|c| c.is_whitespace() || c == '"' || c == '\\' || quote_chars.contains(&c));
+
+        if needs_quotes && !matches!(self, PgValue::Array(_)) {
+            // Arrays escape quotes with a backslash, records by doubling them.
+            let quote = if quote_chars.contains(&'(') {
+                "\"\""
+            } else {
+                "\\\""
+            };
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', quote))
+        } else {
+            text
+        }
+    }
+}
+
+/// psql's text rendering of the value. `NULL` renders as an empty string,
+/// as in psql; callers choose their own NULL marker.
+impl fmt::Display for PgValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgValue::Null => Ok(()),
+            PgValue::Bool(b) => write!(f, "{b}"),
+            PgValue::Int(i) => write!(f, "{i}"),
+            PgValue::Float(v) => write!(f, "{}", format_float(*v)),
+            PgValue::Float4(v) => write!(f, "{}", float_text(*v, FLOAT4_DIGITS)),
+            PgValue::Numeric(n) | PgValue::Text(n) => write!(f, "{n}"),
+            PgValue::DateTime { text, .. } => write!(f, "{text}"),
+            PgValue::Json(v) => write!(f, "{v}"),
+            PgValue::Array(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match item {
+                        PgValue::Null => "NULL".to_string(),
+                        item => item.literal_element(&['{', '}', ',']),
+                    })
+                    .collect();
+                write!(f, "{{{}}}", items.join(","))
+            }
+            PgValue::Composite(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(_, value)| value.literal_element(&['(', ')', ',']))
+                    .collect();
+                write!(f, "({})", fields.join(","))
+            }
+        }
+    }
+}
+
+/// Reads column `index` of `row` as a [`PgValue`].
+pub fn column_value(row: &Row, index: usize) -> PgValue {
+    match row.try_get::<_, AnyValue>(index) {
+        Ok(AnyValue(value)) => value,
+        Err(e) => PgValue::Text(format!("<undecodable value: {e}>")),
+    }
+}
+
+/// Whether values of `ty` are read as the server's text rather than decoded
+/// here: types that are not built in, whose binary format is their own
+/// (hstore excepted), and `timestamptz`, which is sent in UTC but shown in
+/// the session's time zone. Arrays, domains, ranges and composites of such
+/// types are read as text as a whole.
+pub fn needs_text(ty: &Type) -> bool {
+    match ty.kind() {
+        Kind::Array(inner) | Kind::Domain(inner) | Kind::Range(inner) | Kind::Multirange(inner) => {
+            needs_text(inner)
+        }
+        Kind::Composite(fields) => fields.iter().any(|field| needs_text(field.type_())),
+        Kind::Enum(_) | Kind::Pseudo => false,
+        _ => {
+            *ty == Type::TIMESTAMPTZ
+                || (Type::from_oid(ty.oid()).is_none() && ty.name() != "hstore")
+        }
+    }
+}
+
+/// Reads column `index` of a query result whose column is of type `ty`.
+/// When that column was read as text (see [`needs_text`]), the row holds the
+/// server's text for it instead.
+pub fn result_value(row: &Row, index: usize, ty: &Type) -> PgValue {
+    if row.columns()[index].type_() == ty {
+        return column_value(row, index);
+    }
+
+    match row.try_get::<_, Option<String>>(index) {
+        Ok(None) => PgValue::Null,
+        Ok(Some(text)) if *ty == Type::TIMESTAMPTZ => PgValue::DateTime {
+            iso: iso_timestamp(&text),
+            text,
+        },
+        Ok(Some(text)) => PgValue::Text(text),
+        Err(e) => PgValue::Text(format!("<undecodable value: {e}>")),
+    }
+}
+
+/// ISO 8601 form of a `timestamptz` in the server's ISO `DateStyle`, such as
+/// `2024-03-10 12:34:56.5+01`. Other forms (`infinity`, BC dates, other
+/// styles) are kept as they are.
+fn iso_timestamp(text: &str) -> String {
+    let shape = timestamp_shape();
+    match shape.captures(text) {
+        Some(parts) => {
+            let offset = match &parts[3] {
+                "+00" => "Z".to_string(),
+                offset => iso_offset(offset),
+            };
+            format!("{}T{}{offset}", &parts[1], &parts[2])
+        }
+        None => text.to_string(),
+    }
+}
+
+fn timestamp_shape() -> &'static Regex {
+    static SHAPE: OnceLock<Regex> = OnceLock::new();
+    SHAPE.get_or_init(|| {
+        Regex::new(
+            r"^(\d{4,}-\d{2}-\d{2}) (\d{2}:\d{2}:\d{2}(?:\.\d+)?)([+-]\d{2}(?::\d{2}){0,2})$",
+        )
+        .expect("valid regex")
+    })
+}
+
+/// Accepts a value of any type and decodes it with [`decode`].
+struct AnyValue(PgValue);
+
+impl<'a> FromSql<'a> for AnyValue {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, DecodeError> {
+        Ok(AnyValue(decode(ty, raw)?))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, DecodeError> {
+        Ok(AnyValue(PgValue::Null))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Number of bytes the values of `row` took on the wire; NULLs take none.
+pub fn row_size(row: &Row) -> usize {
+    (0..row.len())
+        .map(|index| {
+            row.try_get::<_, RawSize>(index)
+                .map_or(0, |RawSize(size)| size)
+        })
+        .sum()
+}
+
+/// Accepts a value of any type and keeps only its size.
+struct RawSize(usize);
+
+impl<'a> FromSql<'a> for RawSize {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, DecodeError> {
+        Ok(RawSize(raw.len()))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, DecodeError> {
+        Ok(RawSize(0))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Decodes a binary-format value of type `ty`.
+pub fn decode(ty: &Type, raw: &[u8]) -> Result<PgValue, DecodeError> {
+    match ty.kind() {
+        Kind::Enum(_) => return Ok(PgValue::Text(text(raw)?)),
+        Kind::Domain(inner) => return decode(inner, raw),
+        Kind::Array(member) => return decode_array(member, raw),
+        Kind::Range(inner) => return Ok(PgValue::Text(decode_range(inner, raw)?)),
+        Kind::Multirange(inner) => return decode_multirange(inner, raw),
+        Kind::Composite(fields) => {
+            let types: Vec<(String, Type)> = fields
+                .iter()
+                .map(|field| (field.name().to_string(), field.type_().clone()))
+                .collect();
+            return decode_composite(&types, raw);
+        }
+        _ => {}
+    }
+
+    let mut r = Reader(raw);
+    let value = match *ty {
+        Type::BOOL => PgValue::Bool(r.u8()? != 0),
+        Type::INT2 => PgValue::Int(r.i16()?.into()),
+        Type::INT4 => PgValue::Int(r.i32()?.into()),
+        Type::INT8 => PgValue::Int(r.i64()?),
+        Type::OID
+        | Type::XID
+        | Type::CID
+        | Type::REGPROC
+        | Type::REGPROCEDURE
+        | Type::REGOPER
+        | Type::REGOPERATOR
+        | Type::REGCLASS
+        | Type::REGTYPE
+        | Type::REGCONFIG
+        | Type::REGDICTIONARY
+        | Type::REGNAMESPACE
+        | Type::REGROLE
+        | Type::REGCOLLATION => PgValue::Int(r.u32()?.into()),
+        Type::XID8 => PgValue::Numeric(r.u64()?.to_string()),
+        Type::FLOAT4 => PgValue::Float4(r.f32()?),
+        Type::FLOAT8 => PgValue::Float(r.f64()?),
+        Type::NUMERIC => PgValue::Numeric(decode_numeric(raw)?),
+        Type::MONEY => PgValue::Numeric(scaled_integer(r.i64()?, 2)),
+        Type::CHAR => PgValue::Text(char::from(r.u8()?).to_string()),
+        Type::TEXT
+        | Type::VARCHAR
+        | Type::BPCHAR
+        | Type::NAME
+        | Type::UNKNOWN
+        | Type::XML
+        | Type::REFCURSOR
+        | Type::PG_NODE_TREE
+        | Type::PG_NDISTINCT
+        | Type::PG_DEPENDENCIES => PgValue::Text(text(raw)?),
+        Type::JSON => PgValue::Json(serde_json::from_slice(raw)?),
+        Type::JSONB => PgValue::Json(serde_json::from_slice(versioned_body(raw)?)?),
+        Type::JSONPATH => PgValue::Text(text(versioned_body(raw)?)?),
+        Type::UUID => PgValue::Text(uuid::Uuid::from_slice(raw)?.hyphenated().to_string()),
+        Type::BYTEA => PgValue::Text(hex_bytes(raw)),
+        Type::DATE => decode_date(r.i32()?),
+        Type::TIME => {
+            let text = format_time(r.i64()?);
+            PgValue::DateTime {
+                iso: text.clone(),
+                text,
+            }
+        }
+        Type::TIMETZ => {
+            let time = format_time(r.i64()?);
+            // Stored as seconds west of UTC.
+            let offset = format_offset(-r.i32()?);
+            PgValue::DateTime {
+                text: format!("{time}{offset}"),
+                iso: format!("{time}{}", iso_offset(&offset)),
+            }
+        }
+        Type::TIMESTAMP => decode_timestamp(r.i64()?, false),
+        Type::TIMESTAMPTZ => decode_timestamp(r.i64()?, true),
+        Type::INTERVAL => {
+            let micros = r.i64()?;
+            let days = r.i32()?;
+            let months = r.i32()?;
+            PgValue::Text(format_interval(months, days, micros))
+        }
+        Type::INET | Type::CIDR => PgValue::Text(decode_inet(raw, *ty == Type::CIDR)?),
+        Type::MACADDR | Type::MACADDR8 => PgValue::Text(
+            raw.iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(":"),
+        ),
+        Type::BIT | Type::VARBIT => PgValue::Text(decode_bits(raw)?),
+        Type::PG_LSN => {
+            let lsn = r.u64()?;
+            PgValue::Text(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))
+        }
+        Type::TID => {
+            let block = r.u32()?;
+            let offset = r.u16()?;
+            PgValue::Text(format!("({block},{offset})"))
+        }
+        Type::POINT => PgValue::Text(format_point(r.f64()?, r.f64()?)),
+        Type::LSEG => PgValue::Text(format!(
+            "[{},{}]",
+            format_point(r.f64()?, r.f64()?),
+            format_point(r.f64()?, r.f64()?)
+        )),
+        Type::BOX => PgValue::Text(format!(
+            "{},{}",
+            format_point(r.f64()?, r.f64()?),
+            format_point(r.f64()?, r.f64()?)
+        )),
+        Type::LINE => PgValue::Text(format!(
+            "{{{},{},{}}}",
+            format_float(r.f64()?),
+            format_float(r.f64()?),
+            format_float(r.f64()?)
+        )),
+        Type::CIRCLE => PgValue::Text(format!(
+            "<{},{}>",
+            format_point(r.f64()?, r.f64()?),
+            format_float(r.f64()?)
+        )),
+        Type::PATH => {
+            let closed = r.u8()? != 0;
+            let points = read_points(&mut r)?;
+            if closed {
+                PgValue::Text(format!("({points})"))
+            } else {
+                PgValue::Text(format!("[{points}]"))
+            }
+        }
+        Type::POLYGON => PgValue::Text(format!("({})", read_points(&mut r)?)),
+        Type::TS_VECTOR => PgValue::Text(decode_tsvector(raw)?),
+        Type::RECORD => decode_record(raw)?,
+        Type::RECORD_ARRAY => decode_array(&Type::RECORD, raw)?,
+        Type::VOID => PgValue::Null,
+        _ => decode_extension(ty, raw),
+    };
+
+    Ok(value)
+}
+
+/// Best-effort decoding of types that are not built in, for the values that
+/// are not read as text: those nested in anonymous records.
+fn decode_extension(ty: &Type, raw: &[u8]) -> PgValue {
+    match ty.name() {
+        // ltree and friends prefix their text with a version byte.
+        "ltree" | "lquery" | "ltxtquery" => match versioned_body(raw).and_then(text) {
+            Ok(text) => PgValue::Text(text),
+            Err(_) => PgValue::Text(hex_bytes(raw)),
+        },
+        "hstore" => decode_hstore(raw).unwrap_or_else(|_| PgValue::Text(hex_bytes(raw))),
+        _ => match std::str::from_utf8(raw) {
+            Ok(text) if !text.contains('\0') => PgValue::Text(text.to_string()),
+            _ => PgValue::Text(hex_bytes(raw)),
+        },
+    }
+}
+
+/// Sequential big-endian reader over a binary value.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err("value is shorter than its type requires".into());
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(i16::from_be_bytes(self.array()?))
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, DecodeError> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_be_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, DecodeError> {
+        Ok(f64::from_be_bytes(self.array()?))
+    }
+
+    /// A length-prefixed value, `None` for NULL.
+    fn value(&mut self) -> Result<Option<&'a [u8]>, DecodeError> {
+        let len = self.i32()?;
+        if len < 0 {
+            Ok(None)
+        } else {
+            Ok(Some(self.take(len as usize)?))
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn text(raw: &[u8]) -> Result<String, DecodeError> {
+    Ok(std::str::from_utf8(raw)?.to_string())
+}
+
+fn versioned_body(raw: &[u8]) -> Result<&[u8], DecodeError> {
+    match raw.split_first() {
+        Some((1, body)) => Ok(body),
+        _ => Err("unsupported binary format version".into()),
+    }
+}
+
+fn hex_bytes(raw: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + raw.len() * 2);
+    hex.push_str("\\x");
+    for byte in raw {
+        hex.push_str(&format!("{byte:02x}"));
+    }
+    hex
+}
+
+/// Decimal digits of `float8`, from which the server writes exponents.
+const FLOAT8_DIGITS: i32 = 15;
+/// Decimal digits of `real`.
+const FLOAT4_DIGITS: i32 = 6;
+
+fn format_float(value: f64) -> String {
+    float_text(value, FLOAT8_DIGITS)
+}
+
+/// The server's text for a float: its shortest round-trip digits, written
+/// with an exponent (`1e-05`, `1.5e+20`) below 1e-4 and from 10^`digits` up.
+fn float_text<T>(value: T, digits: i32) -> String
+where
+    T: Copy + Into<f64> + fmt::Display + fmt::LowerExp,
+{
+    let wide: f64 = value.into();
+    if wide.is_nan() {
+        return "NaN".to_string();
+    }
+    if wide.is_infinite() {
+        return if wide > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("scientific notation has an exponent");
+    let exponent: i32 = exponent.parse().expect("exponent is an integer");
+    if (-4..digits).contains(&exponent) {
+        value.to_string()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{mantissa}e{sign}{:02}", exponent.unsigned_abs())
+    }
+}
+
+fn float_json(value: f64) -> Value {
+    Number::from_f64(value)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(format_float(value)))
+}
+
+/// Emits a decimal as a JSON number when that loses no precision, and as a
+/// string otherwise (including `NaN` and the infinities).
+fn numeric_to_json(text: &str) -> Value {
+    if let Ok(int) = text.parse::<i64>() {
+        return Value::from(int);
+    }
+
+    let normalized = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    };
+
+    match normalized.parse::<f64>() {
+        Ok(float) if float.is_finite() && float.to_string() == normalized => {
+            Number::from_f64(float).map_or_else(|| Value::String(text.to_string()), Value::Number)
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Formats an integer holding a fixed number of decimal places.
+fn scaled_integer(value: i64, scale: u32) -> String {
+    let divisor = 10_u64.pow(scale);
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    format!(
+        "{sign}{}.{:0width$}",
+        magnitude / divisor,
+        magnitude % divisor,
+        width = scale as usize
+    )
+}
+
+fn decode_numeric(raw: &[u8]) -> Result<String, DecodeError> {
+    let mut r = Reader(raw);
+    let ndigits = r.i16()?;
+    let weight = r.i16()?;
+    let sign = r.u16()?;
+    let dscale = r.u16()?;
+
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let digits = (0..ndigits)
+        .map(|_| r.i16())
+        .collect::<Result<Vec<_>, _>>()?;
+    let digit = |index: i32| -> i16 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+
+    // Digits are base 10000; `weight` is the power of the first one.
+    if weight < 0 {
+        text.push('0');
+    } else {
+        for index in 0..=i32::from(weight) {
+            if index == 0 {
+                text.push_str(&digit(index).to_string());
+            } else {
+                text.push_str(&format!("{:04}", digit(index)));
+            }
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut index = i32::from(weight) + 1;
+        while fraction.len() < usize::from(dscale) {
+            fraction.push_str(&format!("{:04}", digit(index)));
+            index += 1;
+        }
+        fraction.truncate(usize::from(dscale));
+        text.push('.');
+        text.push_str(&fraction);
+    }
+
+    Ok(text)
+}
+
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .expect("2000-01-01 is a valid date")
+}
+
+fn decode_date(days: i32) -> PgValue {
+    let text = match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        days => pg_epoch()
+            .date()
+            .checked_add_signed(Duration::days(days.into()))
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| format!("<date out of range: {days}>")),
+    };
+
+    PgValue::DateTime {
+        iso: text.clone(),
+        text,
+    }
+}
+
+fn decode_timestamp(micros: i64, with_time_zone: bool) -> PgValue {
+    let special = match micros {
+        i64::MAX => Some("infinity"),
+        i64::MIN => Some("-infinity"),
+        _ => None,
+    };
+    if let Some(special) = special {
+        return PgValue::DateTime {
+            text: special.to_string(),
+            iso: special.to_string(),
+        };
+    }
+
+    let Some(timestamp) = pg_epoch().checked_add_signed(Duration::microseconds(micros)) else {
+        return PgValue::Text(format!("<timestamp out of range: {micros}>"));
+    };
+
+    let fraction = fraction_digits(timestamp.and_utc().timestamp_subsec_micros());
+    let date_time = timestamp.format("%Y-%m-%d %H:%M:%S");
+    let iso_date_time = timestamp.format("%Y-%m-%dT%H:%M:%S");
+
+    if with_time_zone {
+        // Values are transferred in UTC regardless of the session time zone.
+        PgValue::DateTime {
+            text: format!("{date_time}{fraction}+00"),
+            iso: format!("{iso_date_time}{fraction}Z"),
+        }
+    } else {
+        PgValue::DateTime {
+            text: format!("{date_time}{fraction}"),
+            iso: format!("{iso_date_time}{fraction}"),
+        }
+    }
+}
+
+/// `.ffffff` with trailing zeros removed, or nothing for whole seconds.
+fn fraction_digits(micros: u32) -> String {
+    if micros == 0 {
+        String::new()
+    } else {
+        format!(".{micros:06}").trim_end_matches('0').to_string()
+    }
+}
+
+fn format_time(micros: i64) -> String {
+    let seconds = micros.div_euclid(1_000_000);
+    let sub_micros = micros.rem_euclid(1_000_000) as u32;
+
+    // 24:00:00 is a valid time value that NaiveTime cannot represent.
+    if seconds == 86_400 {
+        return "24:00:00".to_string();
+    }
+
+    match NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, 0) {
+        Some(time) => format!("{}{}", time.format("%H:%M:%S"), fraction_digits(sub_micros)),
+        None => format!("<time out of range: {micros}>"),
+    }
+}
+
+/// psql's `+HH[:MM[:SS]]` offset notation.
+fn format_offset(seconds_east: i32) -> String {
+    let sign = if seconds_east < 0 { '-' } else { '+' };
+    let total = seconds_east.unsigned_abs();
+    let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+
+    let mut offset = format!("{sign}{hours:02}");
+    if minutes != 0 || seconds != 0 {
+        offset.push_str(&format!(":{minutes:02}"));
+    }
+    if seconds != 0 {
+        offset.push_str(&format!(":{seconds:02}"));
+    }
+    offset
+}
+
+fn iso_offset(offset: &str) -> String {
+    if offset.len() == 3 {
+        format!("{offset}:00")
+    } else {
+        offset.to_string()
+    }
+}
+
+/// Formats an interval the way the server does with `IntervalStyle =
+/// postgres`, e.g. `1 year 2 mons 3 days 04:05:06`. Each part has its own
+/// sign, and a positive part after a negative one is written with `+`, as in
+/// `-1 days +02:00:00`.
+fn format_interval(months: i32, days: i32, micros: i64) -> String {
+    let mut parts = Vec::new();
+    let mut after_negative = false;
+    for (value, unit) in [(months / 12, "year"), (months % 12, "mon"), (days, "day")] {
+        if value != 0 {
+            let sign = if after_negative && value > 0 { "+" } else { "" };
+            let plural = if value == 1 { "" } else { "s" };
+            parts.push(format!("{sign}{value} {unit}{plural}"));
+            after_negative = value < 0;
+        }
+    }
+
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 {
+            "-"
+        } else if after_negative {
+            "+"
+        } else {
+            ""
+        };
+        let total = micros.unsigned_abs();
+        let seconds = total / 1_000_000;
+        parts.push(format!(
+            "{sign}{:02}:{:02}:{:02}{}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60,
+            fraction_digits((total % 1_000_000) as u32)
+        ));
+    }
+
+    parts.join(" ")
+}
+
+fn decode_inet(raw: &[u8], is_cidr: bool) -> Result<String, DecodeError> {
+    let mut r = Reader(raw);
+    let family = r.u8()?;
+    let netmask = r.u8()?;
+    let _is_cidr_flag = r.u8()?;
+    let len = r.u8()?;
+    let bytes = r.take(usize::from(len))?;
+
+    let (addr, max_mask) = match family {
+        2 => (IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes)?)), 32),
+        3 => (
+            IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes)?)),
+            128,
+        ),
+        _ => return Err("unknown inet address family".into()),
+    };
+
+    if netmask == max_mask && !is_cidr {
+        Ok(addr.to_string())
+    } else {
+        Ok(format!("{addr}/{netmask}"))
+    }
+}
+
+fn decode_bits(raw: &[u8]) -> Result<String, DecodeError> {
+    let mut r = Reader(raw);
+    let len = r.i32()?;
+    let bytes = r.0;
+
+    Ok((0..usize::try_from(len)?)
+        .map(|bit| {
+            let byte = bytes.get(bit / 8).copied().unwrap_or(0);
+            if byte & (0x80 >> (bit % 8)) != 0 {
+                '1'
+            } else {
+                '0'
+            }
+        })
+        .collect())
+}
+
+fn format_point(x: f64, y: f64) -> String {
+    format!("({},{})", format_float(x), format_float(y))
+}
+
+fn read_points(r: &mut Reader<'_>) -> Result<String, DecodeError> {
+    let count = r.i32()?;
+    let points = (0..count)
+        .map(|_| Ok(format_point(r.f64()?, r.f64()?)))
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    Ok(points.join(","))
+}
+
+fn decode_tsvector(raw: &[u8]) -> Result<String, DecodeError> {
+    let mut r = Reader(raw);
+    let count = r.i32()?;
+    let mut lexemes = Vec::new();
+
+    for _ in 0..count {
+        let end =
+            r.0.iter()
+                .position(|&b| b == 0)
+                .ok_or("unterminated tsvector lexeme")?;
+        let word = std::str::from_utf8(r.take(end)?)?.replace('\'', "''");
+        r.take(1)?;
+
+        let positions = (0..r.u16()?)
+            .map(|_| {
+                let entry = r.u16()?;
+                let weight = match entry >> 14 {
+                    3 => "A",
+                    2 => "B",
+                    1 => "C",
+                    _ => "",
+                };
+                Ok(format!("{}{weight}", entry & 0x3FFF))
+            })
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+
+        if positions.is_empty() {
+            lexemes.push(format!("'{word}'"));
+        } else {
+            lexemes.push(format!("'{word}':{}", positions.join(",")));
+        }
+    }
+
+    Ok(lexemes.join(" "))
+}
+
+fn decode_hstore(raw: &[u8]) -> Result<PgValue, DecodeError> {
+    let mut r = Reader(raw);
+    let count = r.i32()?;
+    let mut map = Map::new();
+
+    for _ in 0..count {
+        let key = text(r.value()?.ok_or("hstore key is NULL")?)?;
+        let value = match r.value()? {
+            Some(value) => Value::String(text(value)?),
+            None => Value::Null,
+        };
+        map.insert(key, value);
+    }
+
+    Ok(PgValue::Json(Value::Object(map)))
+}
+
+fn decode_array(member: &Type, raw: &[u8]) -> Result<PgValue, DecodeError> {
+    let mut r = Reader(raw);
+    let dimensions = r.i32()?;
+    let _has_nulls = r.i32()?;
+    let _element_oid = r.u32()?;
+
+    let bounds = (0..dimensions)
+        .map(|_| Ok((usize::try_from(r.i32()?)?, r.i32()?)))
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    let lengths: Vec<usize> = bounds.iter().map(|&(len, _)| len).collect();
+
+    let count: usize = if lengths.is_empty() {
+        0
+    } else {
+        lengths.iter().product()
+    };
+    let mut elements = Vec::with_capacity(count);
+    for _ in 0..count {
+        elements.push(match r.value()? {
+            Some(value) => decode(member, value)?,
+            None => PgValue::Null,
+        });
+    }
+
+    let array = nest(&lengths, &mut elements.into_iter());
+    if bounds.iter().all(|&(_, lower)| lower == 1) {
+        return Ok(array);
+    }
+
+    // Like the server, spell out lower bounds other than 1, such as
+    // `[0:2]={1,2,3}`; the value is then text.
+    let dimensions: String = bounds
+        .iter()
+        .map(|&(len, lower)| format!("[{lower}:{}]", i64::from(lower) + len as i64 - 1))
+        .collect();
+    Ok(PgValue::Text(format!("{dimensions}={array}")))
+}
+
+/// Rebuilds the nesting of a multi-dimensional array from its elements in
+/// row-major order.
+fn nest(lengths: &[usize], elements: &mut impl Iterator<Item = PgValue>) -> PgValue {
+    match lengths.split_first() {
+        None => PgValue::Array(Vec::new()),
+        Some((&len, [])) => PgValue::Array(elements.take(len).collect()),
+        Some((&len, rest)) => PgValue::Array((0..len).map(|_| nest(rest, elements)).collect()),
+    }
+}
+
+fn decode_range(inner: &Type, raw: &[u8]) -> Result<String, DecodeError> {
+    const EMPTY: u8 = 0x01;
+    const LOWER_INCLUSIVE: u8 = 0x02;
+    const UPPER_INCLUSIVE: u8 = 0x04;
+    const LOWER_UNBOUNDED: u8 = 0x08;
+    const UPPER_UNBOUNDED: u8 = 0x10;
+
+    let mut r = Reader(raw);
+    let flags = r.u8()?;
+    if flags & EMPTY != 0 {
+        return Ok("empty".to_string());
+    }
+
+    let mut bound = |unbounded: u8| -> Result<String, DecodeError> {
+        if flags & unbounded != 0 {
+            return Ok(String::new());
+        }
+        match r.value()? {
+            Some(value) => Ok(decode(inner, value)?.literal_element(&['[', ']', '(', ')', ','])),
+            None => Ok(String::new()),
+        }
+    };
+
+    let lower = bound(LOWER_UNBOUNDED)?;
+    let upper = bound(UPPER_UNBOUNDED)?;
+    let open = if flags & LOWER_INCLUSIVE != 0 {
+        '['
+    } else {
+        '('
+    };
+    let close = if flags & UPPER_INCLUSIVE != 0 {
+        ']'
+    } else {
+        ')'
+    };
+
+    Ok(format!("{open}{lower},{upper}{close}"))
+}
+
+fn decode_multirange(inner: &Type, raw: &[u8]) -> Result<PgValue, DecodeError> {
+    let mut r = Reader(raw);
+    let count = r.i32()?;
+    let ranges = (0..count)
+        .map(|_| {
+            let range = r.value()?.ok_or("multirange element is NULL")?;
+            decode_range(inner, range)
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+
+    Ok(PgValue::Text(format!("{{{}}}", ranges.join(","))))
+}
+
+fn decode_composite(fields: &[(String, Type)], raw: &[u8]) -> Result<PgValue, DecodeError> {
+    let mut r = Reader(raw);
+    let count = r.i32()?;
+    let mut values = Vec::with_capacity(fields.len());
+
+    for index in 0..usize::try_from(count)? {
+        let _oid = r.u32()?;
+        let value = r.value()?;
+        let (name, ty) = fields
+            .get(index)
+            .ok_or("composite value has more fields than its type")?;
+
+        values.push((
+            name.clone(),
+            match value {
+                Some(value) => decode(ty, value)?,
+                None => PgValue::Null,
+            },
+        ));
+    }
+
+    if !r.is_empty() {
+        return Err("trailing data after composite value".into());
+    }
+
+    Ok(PgValue::Composite(values))
+}
+
+/// Decodes an anonymous record, such as `ROW(1, 'a')`. Fields carry their own
+/// type OIDs; fields of non-built-in types are read as text.
+fn decode_record(raw: &[u8]) -> Result<PgValue, DecodeError> {
+    let mut r = Reader(raw);
+    let count = r.i32()?;
+    let mut values = Vec::new();
+
+    for index in 1..=count {
+        let oid = r.u32()?;
+        let value = match r.value()? {
+            Some(value) => match Type::from_oid(oid) {
+                Some(ty) => decode(&ty, value)?,
+                None => decode_extension(&Type::UNKNOWN, value),
+            },
+            None => PgValue::Null,
+        };
+        values.push((format!("f{index}"), value));
+    }
+
+    Ok(PgValue::Composite(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes `hex`, the output of the type's send function, and renders it
+    /// as psql would.
+    fn text_of(ty: &Type, hex: &str) -> String {
+        let raw: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("valid hex"))
+            .collect();
+        decode(ty, &raw).expect("decodable value").to_string()
+    }
+
+    fn check(ty: &Type, cases: &[(&str, &str)]) {
+        for (hex, expected) in cases {
+            assert_eq!(text_of(ty, hex), *expected, "{ty} {hex}");
+        }
+    }
+
+    #[test]
+    fn numeric() {
+        check(
+            &Type::NUMERIC,
+            &[
+                ("00000000c0000000", "NaN"),
+                ("00000000d0000020", "Infinity"),
+                ("00000000f0000020", "-Infinity"),
+                ("0000000000000000", "0"),
+                ("0000000000000002", "0.00"),
+                ("000200000000000200011388", "1.50"),
+                ("0003000140000004000109291a85", "-12345.6789"),
+                ("00010005000000000001", "100000000000000000000"),
+                ("0001fffe0000000604b0", "0.000012"),
+                (
+                    "0006000200000009000109291a850000000003e8",
+                    "123456789.000000001",
+                ),
+                // round(123456, -3)
+                ("0002000100000000000c0bb8", "123000"),
+            ],
+        );
+    }
+
+    #[test]
+    fn interval() {
+        check(
+            &Type::INTERVAL,
+            &[
+                ("00000000000000000000000000000000", "00:00:00"),
+                (
+                    "000000036c9361a0000000030000000e",
+                    "1 year 2 mons 3 days 04:05:06.5",
+                ),
+                ("000000000000000000000000fffffff4", "-1 years"),
+                ("0000000000000000000000000000000a", "10 mons"),
+                ("00000001ad274800ffffffff00000000", "-1 days +02:00:00"),
+                ("fffffffe52d8b8000000000100000000", "1 day -02:00:00"),
+                (
+                    "ffffffffffeced30fffffffdffffffff",
+                    "-1 mons -3 days -00:00:01.25",
+                ),
+                ("0000000000000000fffffffd00000000", "-3 days"),
+                (
+                    "00000000000000010000000100000001",
+                    "1 mon 1 day 00:00:00.000001",
+                ),
+                ("00000053d1ac10000000000000000000", "100:00:00"),
+            ],
+        );
+    }
+
+    #[test]
+    fn inet_and_cidr() {
+        check(
+            &Type::INET,
+            &[
+                ("02200004c0a80001", "192.168.0.1"),
+                ("020800040a000000", "10.0.0.0/8"),
+                ("0380001000000000000000000000000000000001", "::1"),
+                ("0340001020010db8000000000000000000000001", "2001:db8::1/64"),
+            ],
+        );
+        check(
+            &Type::CIDR,
+            &[
+                ("020801040a000000", "10.0.0.0/8"),
+                ("02200104c0a80101", "192.168.1.1/32"),
+                ("0320011020010db8000000000000000000000000", "2001:db8::/32"),
+            ],
+        );
+    }
+
+    #[test]
+    fn money_is_a_plain_decimal() {
+        check(
+            &Type::MONEY,
+            &[
+                ("00000000000004d2", "12.34"),
+                ("fffffffffffffffb", "-0.05"),
+                ("0000000005f5e100", "1000000.00"),
+            ],
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        check(
+            &Type::INT4_ARRAY,
+            &[
+                (
+                    "00000001000000010000001700000003000000010000000400000001ffffffff0000000400000003",
+                    "{1,NULL,3}",
+                ),
+                ("000000000000000000000017", "{}"),
+                (
+                    "00000002000000010000001700000002000000010000000200000001000000040000000100000004000000020000000400000003ffffffff",
+                    "{{1,2},{3,NULL}}",
+                ),
+                (
+                    "0000000100000000000000170000000300000000000000040000000100000004000000020000000400000003",
+                    "[0:2]={1,2,3}",
+                ),
+                (
+                    "00000002000000000000001700000002ffffffff000000010000000100000004000000050000000400000006",
+                    "[-1:0][1:1]={{5},{6}}",
+                ),
+            ],
+        );
+        check(
+            &Type::TEXT_ARRAY,
+            &[
+                (
+                    "0000000100000001000000190000000500000001000000016100000003622063ffffffff000000044e554c4c00000000",
+                    r#"{a,"b c",NULL,"NULL",""}"#,
+                ),
+                (
+                    "00000001000000000000001900000002000000010000000378227900000003612c62",
+                    r#"{"x\"y","a,b"}"#,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn floats() {
+        check(
+            &Type::FLOAT4,
+            &[
+                ("3dcccccd", "0.1"),
+                ("49742400", "1e+06"),
+                ("47f12000", "123456"),
+                ("34210fb0", "1.5e-07"),
+                ("7fc00000", "NaN"),
+                ("ff800000", "-Infinity"),
+                ("7f7fc99e", "3.4e+38"),
+                ("80000000", "-0"),
+            ],
+        );
+        check(
+            &Type::FLOAT8,
+            &[
+                ("3fb999999999999a", "0.1"),
+                ("430c6bf526340000", "1e+15"),
+                ("42dc12218377de40", "123456789012345"),
+                ("3f1a36e2eb1c432d", "0.0001"),
+                ("3ee4f8b588e368f1", "1e-05"),
+                ("80002e055c9a3f6c", "-2.5e-310"),
+                ("8000000000000000", "-0"),
+                ("7ff0000000000000", "Infinity"),
+                ("7fefffffffffffff", "1.7976931348623157e+308"),
+            ],
+        );
+    }
+
+    #[test]
+    fn float4_json_keeps_its_shortest_digits() {
+        assert_eq!(PgValue::Float4(0.1).to_json(), serde_json::json!(0.1));
+        assert_eq!(
+            PgValue::Float4(f32::NAN).to_json(),
+            serde_json::json!("NaN")
+        );
+    }
+}
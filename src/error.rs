@@ -0,0 +1,387 @@
+use serde::Serialize;
+use thiserror::Error;
+
+/// Errors reported by dcon.
+///
+/// Every variant maps to a stable process exit code so scripts can branch
+/// on the kind of failure:
+///
+/// | Code | Meaning |
+/// |------|---------|
+/// | 0    | Success |
+/// | 1    | Local I/O error (terminal, files) |
+/// | 2    | Invalid arguments, input data or configuration |
+/// | 3    | Could not connect to the server, or the connection was lost |
+/// | 4    | Authentication failed or a password is required |
+/// | 5    | The server rejected a SQL statement |
+/// | 130  | Cancelled by the user |
+#[derive(Debug, Error)]
+pub enum DconError {
+    #[error("Failed to connect to PostgreSQL: {0}")]
+    Connection(String),
+
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+
+    #[error("The server requested a password but none was supplied")]
+    PasswordRequired,
+
+    #[error("{0}")]
+    Sql(Box<SqlError>),
+
+    #[error("{0}")]
+    UserInput(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A statement rejected by the server, with the fields of its error report.
+#[derive(Debug, Serialize)]
+pub struct SqlError {
+    /// Severity as reported by the server (`ERROR`, `FATAL`, ...)
+    pub severity: String,
+    /// Five-character SQLSTATE, absent for client-side failures
+    pub code: Option<String>,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// Call stack context, e.g. the PL/pgSQL line that failed
+    pub context: Option<String>,
+    /// 1-based character offset into the statement
+    pub position: Option<u32>,
+    /// The statement that failed, when known
+    pub statement: Option<String>,
+    /// Script the statement was read from, `-` for stdin
+    pub file: Option<String>,
+    /// 1-based line of the script where the statement starts
+    pub line: Option<usize>,
+}
+
+impl SqlError {
+    /// A failure noticed by the client rather than reported by the server,
+    /// so without a SQLSTATE.
+    pub fn client(message: String) -> Self {
+        SqlError {
+            severity: "ERROR".to_string(),
+            code: None,
+            message,
+            detail: None,
+            hint: None,
+            context: None,
+            position: None,
+            statement: None,
+            file: None,
+            line: None,
+        }
+    }
+}
+
+impl std::fmt::Display for SqlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} (SQLSTATE {code})", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl DconError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            DconError::Io(_) => 1,
+            DconError::UserInput(_) => 2,
+            DconError::Connection(_) => 3,
+            DconError::Auth(_) | DconError::PasswordRequired => 4,
+            DconError::Sql(_) => 5,
+            DconError::Cancelled => 130,
+        }
+    }
+
+    /// Short machine-readable name of the variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DconError::Connection(_) => "connection",
+            DconError::Auth(_) | DconError::PasswordRequired => "auth",
+            DconError::Sql(_) => "sql",
+            DconError::UserInput(_) => "input",
+            DconError::Cancelled => "cancelled",
+            DconError::Io(_) => "io",
+        }
+    }
+
+    /// Records the statement that produced a SQL error so the failing
+    /// position can be pointed out.
+    pub fn with_statement(self, sql: &str) -> Self {
+        self.with_statement_at(sql, 0)
+    }
+
+    /// Like [`DconError::with_statement`], for a statement that was sent
+    /// inside a longer one, after its first `offset` characters. Positions
+    /// outside the statement are dropped.
+    pub fn with_statement_at(mut self, sql: &str, offset: usize) -> Self {
+        if let DconError::Sql(error) = &mut self {
+            error.position = error
+                .position
+                .and_then(|position| position.checked_sub(u32::try_from(offset).ok()?))
+                .filter(|&position| position >= 1 && position as usize <= sql.chars().count());
+            error.statement = Some(sql.to_string());
+        }
+        self
+    }
+
+    /// Records the script and line a failing statement was read from.
+    pub fn with_location(mut self, file: &str, line: usize) -> Self {
+        if let DconError::Sql(error) = &mut self {
+            error.file = Some(file.to_string());
+            error.line = Some(line);
+        }
+        self
+    }
+
+    /// Renders the error the way psql does: severity, SQLSTATE and message,
+    /// the offending line with a caret under the failing token, then any
+    /// detail, hint and context lines.
+    pub fn report(&self) -> String {
+        let DconError::Sql(error) = self else {
+            return format!("Error: {self}");
+        };
+
+        let mut report = match (&error.file, error.line) {
+            (Some(file), Some(line)) => format!("{file}:{line}: "),
+            _ => String::new(),
+        };
+        match &error.code {
+            Some(code) => {
+                report.push_str(&format!("{}:  {code}: {}", error.severity, error.message))
+            }
+            None => report.push_str(&format!("{}:  {}", error.severity, error.message)),
+        }
+
+        if let (Some(position), Some(statement)) = (error.position, &error.statement) {
+            if let Some(pointer) = position_pointer(statement, position as usize) {
+                report.push('\n');
+                report.push_str(&pointer);
+            }
+        }
+        if let Some(detail) = &error.detail {
+            report.push_str(&format!("\nDETAIL:  {detail}"));
+        }
+        if let Some(hint) = &error.hint {
+            report.push_str(&format!("\nHINT:  {hint}"));
+        }
+        if let Some(context) = &error.context {
+            report.push_str(&format!("\nCONTEXT:  {context}"));
+        }
+
+        report
+    }
+
+    /// The error as a structured object for `--format json`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = match self {
+            DconError::Sql(error) => serde_json::json!(error),
+            _ => serde_json::json!({ "message": self.to_string() }),
+        };
+        error["kind"] = serde_json::json!(self.kind());
+        error["exit_code"] = serde_json::json!(self.exit_code());
+
+        serde_json::json!({ "error": error })
+    }
+
+    /// Wraps an I/O error with a description of what was being done.
+    pub fn io(e: std::io::Error, context: impl std::fmt::Display) -> Self {
+        DconError::Io(std::io::Error::new(e.kind(), format!("{context}: {e}")))
+    }
+
+    /// Classifies an error raised while establishing a connection.
+    pub fn from_connect(e: tokio_postgres::Error) -> Self {
+        if let Some(db_error) = e.as_db_error() {
+            // Class 28: invalid authorization specification
+            if db_error.code().code().starts_with("28") {
+                return DconError::Auth(db_error.message().to_string());
+            }
+            return DconError::Connection(db_error.message().to_string());
+        }
+
+        if is_password_missing(&e) {
+            return DconError::PasswordRequired;
+        }
+
+        DconError::Connection(error_chain(&e))
+    }
+}
+
+/// Whether the connection failed because the server asked for a password and
+/// none was configured. tokio-postgres has no error kind for this: it raises
+/// a configuration error whose source reads "password missing". The tests
+/// pin that down against a server asking for a password, so a driver update
+/// that changes it fails them instead of silently losing the prompt.
+fn is_password_missing(e: &tokio_postgres::Error) -> bool {
+    e.as_db_error().is_none()
+        && std::error::Error::source(e)
+            .is_some_and(|source| source.to_string() == "password missing")
+}
+
+impl From<tokio_postgres::Error> for DconError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        if let Some(db_error) = e.as_db_error() {
+            let position = match db_error.position() {
+                Some(tokio_postgres::error::ErrorPosition::Original(position)) => Some(*position),
+                _ => None,
+            };
+
+            return DconError::Sql(Box::new(SqlError {
+                severity: db_error.severity().to_string(),
+                code: Some(db_error.code().code().to_string()),
+                message: db_error.message().to_string(),
+                detail: db_error.detail().map(str::to_string),
+                hint: db_error.hint().map(str::to_string),
+                context: db_error.where_().map(str::to_string),
+                position,
+                statement: None,
+                file: None,
+                line: None,
+            }));
+        }
+
+        if e.is_closed() {
+            return DconError::Connection(error_chain(&e));
+        }
+
+        DconError::Sql(Box::new(SqlError::client(error_chain(&e))))
+    }
+}
+
+impl From<serde_json::Error> for DconError {
+    fn from(e: serde_json::Error) -> Self {
+        DconError::UserInput(format!("Invalid JSON: {e}"))
+    }
+}
+
+/// Builds psql's `LINE n: ...` excerpt with a caret under the character at
+/// the 1-based `position` of `statement`.
+fn position_pointer(statement: &str, position: usize) -> Option<String> {
+    let offset = position.checked_sub(1)?;
+    if offset > statement.chars().count() {
+        return None;
+    }
+
+    let mut line_number = 1;
+    let mut line_start = 0;
+    for (index, c) in statement.chars().enumerate().take(offset) {
+        if c == '\n' {
+            line_number += 1;
+            line_start = index + 1;
+        }
+    }
+
+    let line: String = statement
+        .chars()
+        .skip(line_start)
+        .take_while(|&c| c != '\n')
+        .collect();
+    let label = format!("LINE {line_number}: ");
+
+    // Keep tabs so the caret lines up with the echoed line.
+    let padding: String = line
+        .chars()
+        .take(offset - line_start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    Some(format!(
+        "{label}{}\n{}{padding}^",
+        line.trim_end_matches('\r'),
+        " ".repeat(label.len())
+    ))
+}
+
+/// Joins an error with its sources, since tokio-postgres keeps the useful
+/// part ("connection refused", "password missing") in the source.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_postgres::NoTls;
+
+    /// Connects without a password to a server that answers the startup
+    /// message with `request`, an authentication request.
+    async fn connect_error(request: &'static [u8]) -> tokio_postgres::Error {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("address").port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            let len = socket.read_i32().await.expect("startup length");
+            let mut startup = vec![0; len as usize - 4];
+            socket.read_exact(&mut startup).await.expect("startup");
+            socket.write_all(request).await.expect("request");
+            // Wait for the client to hang up.
+            let _ = socket.read(&mut [0; 64]).await;
+        });
+
+        let config = format!("host=127.0.0.1 port={port} user=alice sslmode=disable");
+        match tokio_postgres::connect(&config, NoTls).await {
+            Ok(_) => panic!("connected without a password"),
+            Err(e) => e,
+        }
+    }
+
+    #[tokio::test]
+    async fn cleartext_request_without_password() {
+        let e = connect_error(b"R\0\0\0\x08\0\0\0\x03").await;
+        assert!(matches!(
+            DconError::from_connect(e),
+            DconError::PasswordRequired
+        ));
+    }
+
+    #[tokio::test]
+    async fn md5_request_without_password() {
+        let e = connect_error(b"R\0\0\0\x0c\0\0\0\x05salt").await;
+        assert!(matches!(
+            DconError::from_connect(e),
+            DconError::PasswordRequired
+        ));
+    }
+
+    #[tokio::test]
+    async fn other_failures_are_connection_errors() {
+        // An unexpected message instead of an authentication request
+        let e = connect_error(b"Z\0\0\0\x05I").await;
+        assert!(matches!(
+            DconError::from_connect(e),
+            DconError::Connection(_)
+        ));
+    }
+
+    #[test]
+    fn positions_map_back_to_the_embedded_statement() {
+        let at = |position| {
+            let mut error = SqlError::client("failed".to_string());
+            error.position = Some(position);
+            match DconError::Sql(Box::new(error)).with_statement_at("select x", 10) {
+                DconError::Sql(error) => error.position,
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(at(11), Some(1));
+        assert_eq!(at(18), Some(8));
+        assert_eq!(at(10), None);
+        assert_eq!(at(19), None);
+    }
+}
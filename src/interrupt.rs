@@ -0,0 +1,96 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
+
+/// Statements that Ctrl-C cancels instead of ending the process.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+/// Reasons to ignore Ctrl-C when no statement runs, such as a pager that
+/// handles it itself.
+static HELD: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPTED: Notify = Notify::const_new();
+/// Set when Ctrl-C cancels a statement, which may have ended before the
+/// cancel request reached the server.
+static PRESSED: AtomicBool = AtomicBool::new(false);
+
+/// Handles Ctrl-C for the rest of the process: a running statement is
+/// cancelled (see [`Running`]), and otherwise, unless a [`Hold`] is in
+/// place, `report` is called and the process exits with status 130.
+pub fn install(report: impl Fn() + Send + 'static) {
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if RUNNING.load(Ordering::SeqCst) > 0 {
+                PRESSED.store(true, Ordering::SeqCst);
+                INTERRUPTED.notify_waiters();
+            } else if HELD.load(Ordering::SeqCst) == 0 {
+                report();
+                std::process::exit(130);
+            }
+        }
+    });
+}
+
+/// Whether Ctrl-C was pressed while a statement ran since the last call,
+/// for scripts to stop even when the cancel request came too late.
+pub fn take_pressed() -> bool {
+    PRESSED.swap(false, Ordering::SeqCst)
+}
+
+/// Marks a statement as running while it lives, so that Ctrl-C wakes
+/// [`Running::interrupted`] instead of ending the process.
+pub struct Running(Pin<Box<Notified<'static>>>);
+
+impl Running {
+    pub fn start() -> Self {
+        // `notify_waiters` stores nothing for futures created later, so this
+        // one exists, and waits, before Ctrl-C can see the statement running.
+        let mut notified = Box::pin(INTERRUPTED.notified());
+        notified.as_mut().enable();
+        RUNNING.fetch_add(1, Ordering::SeqCst);
+        Running(notified)
+    }
+
+    /// Completes when Ctrl-C is pressed.
+    pub async fn interrupted(&mut self) {
+        self.0.as_mut().await;
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Keeps Ctrl-C from ending the process while it lives, for interactive
+/// mode, where it discards the input line, and for the pager.
+pub struct Hold(());
+
+impl Hold {
+    pub fn start() -> Self {
+        HELD.fetch_add(1, Ordering::SeqCst);
+        Hold(())
+    }
+}
+
+impl Drop for Hold {
+    fn drop(&mut self) {
+        HELD.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn ctrl_c_before_the_first_poll_is_not_lost() {
+        let mut running = Running::start();
+        // What the handler does when Ctrl-C comes in at this point.
+        INTERRUPTED.notify_waiters();
+
+        let woken = tokio::time::timeout(Duration::from_secs(1), running.interrupted()).await;
+        assert!(woken.is_ok());
+    }
+}
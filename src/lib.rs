@@ -1,3 +1,7 @@
 pub mod cli;
+pub mod config;
 pub mod db;
+pub mod error;
+pub mod interrupt;
 pub mod models;
+pub mod status;
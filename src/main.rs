@@ -1,25 +1,37 @@
-use std::error::Error;
+use std::io::IsTerminal;
+use std::process::ExitCode;
 
 use clap::Parser;
 use colored::*;
 use url::Url;
 
 mod cli;
+mod config;
 mod db;
+mod error;
+mod interrupt;
 mod models;
+mod status;
 
-use cli::{Commands, OutputFormat};
-use models::connection::ConnectionConfig;
+use cli::csv::{self, CsvOptions, LineEnding};
+use cli::variables::{self, Variables};
+use cli::{Commands, DisplayOptions, ExpandedMode, OutputFormat};
+use db::params::QueryParams;
+use error::DconError;
+use models::connection::{ConnectionConfig, SessionTimeouts, SslMode};
+use models::profile::{DconConfig, Profile};
 
 use crate::{
     cli::{
         commands::{
-            execute_connect, execute_crud_command, execute_database_command,
-            execute_interactive_mode, execute_query, execute_table_command,
+            connect, execute_connect, execute_crud_command, execute_database_command,
+            execute_interactive_mode, execute_profile_command, execute_query,
+            execute_table_command, read_scripts, ScriptOptions,
         },
-        CrudCommands, TableCommands,
+        repl::{history_path, ReplOptions},
+        CrudCommands, DatabaseCommands, TableCommands,
     },
-    db::client::PostgresClient,
+    config::{env_profile, load_config, service_profile},
 };
 
 #[derive(Parser)]
@@ -27,44 +39,153 @@ use crate::{
 #[command(about = "A PostgreSQL CLI Tool for Database Operations")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 pub struct Cli {
-    /// Database Host
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    /// Named profile from the config file
+    #[arg(long, env = "DCON_PROFILE")]
+    pub profile: Option<String>,
 
-    /// Database Port
-    #[arg(short = 'P', long, default_value = "5432")]
-    pub port: u16,
+    /// Database Host [default: localhost]
+    #[arg(short = 'H', long)]
+    pub host: Option<String>,
 
-    /// Username
-    #[arg(short, long, default_value = "postgres")]
-    pub user: String,
+    /// Database Port [default: 5432]
+    #[arg(short = 'P', long)]
+    pub port: Option<u16>,
 
-    /// Password (will prompt if not provided)
+    /// Username [default: postgres]
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Password (prompted for if the server requires one and none is found)
     #[arg(long)]
     pub password: Option<String>,
 
-    /// Database Name
-    #[arg(short, long, default_value = "postgres")]
-    pub database: String,
+    /// Never prompt for a password; fail if the server requires one
+    #[arg(short = 'w', long)]
+    pub no_password: bool,
+
+    /// Database Name [default: postgres]
+    #[arg(short, long)]
+    pub database: Option<String>,
 
     /// Full connection URL (overrides other connection options)
     #[arg(long)]
     pub url: Option<String>,
 
-    /// Output format
-    #[arg(long, default_value = "table")]
-    pub format: OutputFormat,
+    /// SSL mode (disable, prefer, require, verify-ca, verify-full)
+    #[arg(long)]
+    pub sslmode: Option<SslMode>,
+
+    /// Root certificate file used to verify the server certificate
+    #[arg(long)]
+    pub sslrootcert: Option<String>,
+
+    /// Client certificate file
+    #[arg(long)]
+    pub sslcert: Option<String>,
+
+    /// Client private key file (PKCS#8 PEM)
+    #[arg(long)]
+    pub sslkey: Option<String>,
 
-    /// Disable colored output
+    /// Schema searched first for unqualified table names
+    #[arg(long)]
+    pub schema: Option<String>,
+
+    /// Session search_path, e.g. "sales, public"
+    #[arg(long)]
+    pub search_path: Option<String>,
+
+    /// Abort statements that run longer than this, e.g. 30s (statement_timeout)
+    #[arg(long, value_name = "DURATION")]
+    pub statement_timeout: Option<String>,
+
+    /// Give up waiting for a lock after this long, e.g. 5s (lock_timeout)
+    #[arg(long, value_name = "DURATION")]
+    pub lock_timeout: Option<String>,
+
+    /// Close the session when a transaction is left idle this long
+    /// (idle_in_transaction_session_timeout)
+    #[arg(long, value_name = "DURATION")]
+    pub idle_in_transaction_timeout: Option<String>,
+
+    /// Output format [default: table]
+    #[arg(long)]
+    pub format: Option<OutputFormat>,
+
+    /// Disable colored output (also when NO_COLOR is set or stdout is not a
+    /// terminal)
     #[arg(long)]
     pub no_color: bool,
 
+    /// Only print results and errors, without the banner or status messages
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Show each row as a block of column/value lines: on, off, or auto to do
+    /// so only when rows are wider than the terminal (-x alone means on)
+    #[arg(
+        short = 'x',
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "on",
+        default_value_t = ExpandedMode::Off
+    )]
+    pub expanded: ExpandedMode,
+
+    /// Never send long results through the pager ($DCON_PAGER, $PAGER or
+    /// less)
+    #[arg(long)]
+    pub no_pager: bool,
+
+    /// Field delimiter for CSV output, e.g. ';' or '\t'
+    #[arg(long, value_parser = csv::parse_char, default_value = ",")]
+    pub csv_delimiter: char,
+
+    /// Quote character for CSV output
+    #[arg(long, value_parser = csv::parse_char, default_value = "\"")]
+    pub csv_quote: char,
+
+    /// Leave out the header line in CSV output
+    #[arg(long)]
+    pub csv_no_header: bool,
+
+    /// Text written for NULL in CSV output [default: empty]
+    #[arg(long, default_value = "", hide_default_value = true)]
+    pub csv_null: String,
+
+    /// Line ending for CSV output (lf, crlf)
+    #[arg(long, default_value_t = LineEnding::Lf)]
+    pub csv_line_ending: LineEnding,
+
+    /// Print query results this many rows at a time as they arrive, without
+    /// reading the whole result first (0 reads it all) [default: 1000, or 0
+    /// in table format]
+    #[arg(long, env = "DCON_FETCH_COUNT")]
+    pub fetch_count: Option<usize>,
+
+    /// Report each statement's time, time to first row, rows and bytes
+    /// received
+    #[arg(long)]
+    pub timing: bool,
+
+    /// Also report the server's planning and execution time and buffer
+    /// usage, running each read-only query again under EXPLAIN ANALYZE
+    /// (implies --timing)
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Set a variable, referred to in SQL as :NAME, :'NAME' or :"NAME"
+    /// (may be repeated)
+    #[arg(short, long = "var", value_name = "NAME=VALUE", value_parser = variables::parse_assignment)]
+    pub vars: Vec<(String, String)>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 fn print_banner() {
-    println!(
+    eprintln!(
         "{}",
         r#"
 ╔══════════════════════════════════════════════════════════════╗
@@ -76,11 +197,14 @@ fn print_banner() {
     );
 }
 
-fn parse_connection_url(url: &str) -> Result<ConnectionConfig, Box<dyn std::error::Error>> {
-    let parsed_url = Url::parse(url).unwrap();
+fn parse_connection_url(url: &str) -> Result<ConnectionConfig, DconError> {
+    let parsed_url = Url::parse(url)
+        .map_err(|e| DconError::UserInput(format!("Invalid connection URL: {e}")))?;
 
     if parsed_url.scheme() != "postgresql" && parsed_url.scheme() != "postgres" {
-        return Err("URL must use postgresql:// or postgres:// scheme".into());
+        return Err(DconError::UserInput(
+            "URL must use postgresql:// or postgres:// scheme".to_string(),
+        ));
     }
 
     let host = parsed_url.host_str().unwrap_or("localhost").to_string();
@@ -98,66 +222,229 @@ fn parse_connection_url(url: &str) -> Result<ConnectionConfig, Box<dyn std::erro
         database.to_string()
     };
 
-    Ok(ConnectionConfig {
+    let mut config = ConnectionConfig {
         host,
         port,
         user,
         password,
         database,
-    })
+        sslmode: SslMode::default(),
+        sslrootcert: None,
+        sslcert: None,
+        sslkey: None,
+        schema: None,
+        search_path: None,
+        timeouts: SessionTimeouts::default(),
+    };
+
+    for (key, value) in parsed_url.query_pairs() {
+        match key.as_ref() {
+            "sslmode" => config.sslmode = value.parse().map_err(DconError::UserInput)?,
+            "sslrootcert" => config.sslrootcert = Some(value.into_owned()),
+            "sslcert" => config.sslcert = Some(value.into_owned()),
+            "sslkey" => config.sslkey = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+
+    Ok(config)
 }
 
-fn get_connection_config(cli: &Cli) -> Result<ConnectionConfig, Box<dyn Error>> {
-    if let Some(url) = &cli.url {
-        parse_connection_url(url)
-    } else {
-        let password = if let Some(pwd) = &cli.password {
-            Some(pwd.clone())
-        } else {
-            // In a real implementation, you'd use a secure password prompt here
-            // For now, we'll use a default or prompt
-            use std::io::{self, Write};
-            print!("Enter password: ");
-            io::stdout().flush()?;
-            let mut password = String::new();
-            io::stdin().read_line(&mut password)?;
-            let password = password.trim().to_string();
-            if password.is_empty() {
-                None
-            } else {
-                Some(password)
-            }
-        };
+/// Applies the SSL flags given on the command line, which take precedence
+/// over values from the connection URL.
+fn apply_ssl_options(cli: &Cli, config: &mut ConnectionConfig) {
+    if let Some(sslmode) = cli.sslmode {
+        config.sslmode = sslmode;
+    }
+    if cli.sslrootcert.is_some() {
+        config.sslrootcert.clone_from(&cli.sslrootcert);
+    }
+    if cli.sslcert.is_some() {
+        config.sslcert.clone_from(&cli.sslcert);
+    }
+    if cli.sslkey.is_some() {
+        config.sslkey.clone_from(&cli.sslkey);
+    }
+}
 
-        Ok(ConnectionConfig {
-            host: cli.host.clone(),
-            port: cli.port,
-            user: cli.user.clone(),
-            password,
-            database: cli.database.clone(),
-        })
+/// The settings given explicitly on the command line.
+fn cli_profile(cli: &Cli) -> Profile {
+    Profile {
+        host: cli.host.clone(),
+        port: cli.port,
+        user: cli.user.clone(),
+        database: cli.database.clone(),
+        password: cli.password.clone(),
+        sslmode: cli.sslmode,
+        sslrootcert: cli.sslrootcert.clone(),
+        sslcert: cli.sslcert.clone(),
+        sslkey: cli.sslkey.clone(),
+        schema: cli.schema.clone(),
+        search_path: cli.search_path.clone(),
+        format: cli.format.clone(),
+        color: cli.no_color.then_some(false),
     }
 }
 
+/// Resolves the effective settings. Precedence, highest first: command-line
+/// flags, the selected profile (`--profile`, `DCON_PROFILE` or the config
+/// file's `default_profile`), the `PGSERVICE` service definition,
+/// environment variables, built-in defaults.
+fn resolve_settings(cli: &Cli, dcon_config: &DconConfig) -> Result<Profile, DconError> {
+    let profile = match selected_profile(cli, dcon_config) {
+        Some(name) => dcon_config.profiles.get(name).cloned().ok_or_else(|| {
+            DconError::UserInput(format!("Profile '{name}' not found in config file"))
+        })?,
+        None => Profile::default(),
+    };
+
+    Ok(cli_profile(cli)
+        .or(profile)
+        .or(service_profile()?)
+        .or(env_profile()?))
+}
+
+/// Name of the profile in use, if any.
+fn selected_profile<'a>(cli: &'a Cli, dcon_config: &'a DconConfig) -> Option<&'a String> {
+    cli.profile
+        .as_ref()
+        .or(dcon_config.default_profile.as_ref())
+}
+
+fn get_connection_config(cli: &Cli, settings: &Profile) -> Result<ConnectionConfig, DconError> {
+    let mut config = if let Some(url) = &cli.url {
+        let mut config = parse_connection_url(url)?;
+        apply_ssl_options(cli, &mut config);
+        if config.password.is_none() {
+            config.password.clone_from(&settings.password);
+        }
+        config.schema.clone_from(&settings.schema);
+        config.search_path.clone_from(&settings.search_path);
+        config
+    } else {
+        let settings = settings.clone();
+        ConnectionConfig {
+            host: settings.host.unwrap_or_else(|| "localhost".to_string()),
+            port: settings.port.unwrap_or(5432),
+            user: settings.user.unwrap_or_else(|| "postgres".to_string()),
+            password: settings.password,
+            database: settings.database.unwrap_or_else(|| "postgres".to_string()),
+            sslmode: settings.sslmode.unwrap_or_default(),
+            sslrootcert: settings.sslrootcert,
+            sslcert: settings.sslcert,
+            sslkey: settings.sslkey,
+            schema: settings.schema,
+            search_path: settings.search_path,
+            timeouts: SessionTimeouts::default(),
+        }
+    };
+    config.timeouts = SessionTimeouts {
+        statement: cli.statement_timeout.clone(),
+        lock: cli.lock_timeout.clone(),
+        idle_in_transaction: cli.idle_in_transaction_timeout.clone(),
+    };
+
+    Ok(config)
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    if !cli.no_color {
-        print_banner();
+    let (dcon_config, settings) =
+        match load_config().and_then(|c| resolve_settings(&cli, &c).map(|s| (c, s))) {
+            Ok(loaded) => loaded,
+            Err(e) => return report_error(&e, &cli.format.clone().unwrap_or(OutputFormat::Table)),
+        };
+    let profile_name = selected_profile(&cli, &dcon_config).map(String::as_str);
+    let format = settings.format.clone().unwrap_or(OutputFormat::Table);
+
+    status::set_quiet(cli.quiet);
+    if use_color(&settings) {
+        if !cli.quiet {
+            print_banner();
+        }
+    } else {
+        colored::control::set_override(false);
+    }
+
+    let interrupted_format = format.clone();
+    interrupt::install(move || {
+        report_error(&DconError::Cancelled, &interrupted_format);
+    });
+
+    match run(&cli, &settings, profile_name, &format).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => report_error(&e, &format),
+    }
+}
+
+/// Colors are used unless turned off by `--no-color`, the profile or
+/// `NO_COLOR` (see no-color.org), and only when stdout is a terminal, so
+/// piped results carry no escape codes.
+fn use_color(settings: &Profile) -> bool {
+    settings.color != Some(false)
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stdout().is_terminal()
+}
+
+/// Prints the error to stderr, as a JSON object when JSON output was asked
+/// for, and returns the matching exit code.
+fn report_error(e: &DconError, format: &OutputFormat) -> ExitCode {
+    match (e, format) {
+        (_, OutputFormat::Json) => eprintln!("{}", e.to_json()),
+        (DconError::Cancelled, _) => eprintln!("{}", "Operation cancelled.".yellow()),
+        _ => eprintln!("{}", e.report().red()),
+    }
+
+    ExitCode::from(e.exit_code())
+}
+
+async fn run(
+    cli: &Cli,
+    settings: &Profile,
+    profile_name: Option<&str>,
+    format: &OutputFormat,
+) -> Result<(), DconError> {
+    let display = DisplayOptions {
+        expanded: cli.expanded,
+        pager: !cli.no_pager,
+        csv: CsvOptions {
+            delimiter: cli.csv_delimiter,
+            quote: cli.csv_quote,
+            header: !cli.csv_no_header,
+            null: cli.csv_null.clone(),
+            line_ending: cli.csv_line_ending,
+        },
+        fetch_count: cli.fetch_count,
+        timing: cli.timing || cli.stats,
+        stats: cli.stats,
+    };
+    display.csv.validate()?;
+    let variables: Variables = cli.vars.iter().cloned().collect();
+
+    if let Commands::Profile(profile_cmd) = &cli.command {
+        return execute_profile_command(profile_cmd, format, &display);
     }
 
-    let config = get_connection_config(&cli)?;
+    let config = get_connection_config(cli, settings)?;
 
     match &cli.command {
         Commands::Connect => {
-            let client = PostgresClient::new(&config).await?;
-            execute_connect(&client, &cli.format).await?;
+            let client = connect(&config, cli.no_password).await?;
+            execute_connect(&client, format, &display).await?;
         }
 
         Commands::Database(db_cmd) => {
-            let client = PostgresClient::new(&config).await?;
-            execute_database_command(&client, db_cmd, &cli.format).await?;
+            let mut target_config = config.clone();
+            if let DatabaseCommands::Info {
+                name: Some(db_name),
+            } = db_cmd
+            {
+                target_config.database.clone_from(db_name);
+            }
+            let client = connect(&target_config, cli.no_password).await?;
+            execute_database_command(&client, db_cmd, format, &display).await?;
         }
 
         Commands::Table(table_cmd) => {
@@ -165,8 +452,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
             if let Some(db_name) = get_database_from_table_command(table_cmd) {
                 target_config.database = db_name;
             }
-            let client = PostgresClient::new(&target_config).await?;
-            execute_table_command(&client, table_cmd, &cli.format).await?;
+            let client = connect(&target_config, cli.no_password).await?;
+            execute_table_command(&client, table_cmd, format, &display).await?;
         }
 
         Commands::Crud(crud_cmd) => {
@@ -174,27 +461,81 @@ async fn main() -> Result<(), Box<dyn Error>> {
             if let Some(db_name) = get_database_from_crud_command(crud_cmd) {
                 target_config.database = db_name;
             }
-            let client = PostgresClient::new(&target_config).await?;
-            execute_crud_command(&client, crud_cmd, &cli.format).await?;
+            let client = connect(&target_config, cli.no_password).await?;
+            execute_crud_command(&client, crud_cmd, format, &display).await?;
         }
 
-        Commands::Query { sql, database } => {
+        Commands::Query {
+            sql,
+            file,
+            param,
+            param_type,
+            single_transaction,
+            continue_on_error,
+            database,
+        } => {
+            // Read the script first, so a missing file fails before connecting.
+            let scripts = read_scripts(sql.as_deref(), file)?;
+            if param_type.len() > param.len() {
+                return Err(DconError::UserInput(
+                    "--param-type given more often than --param".to_string(),
+                ));
+            }
+            let options = ScriptOptions {
+                single_transaction: *single_transaction,
+                continue_on_error: *continue_on_error,
+                params: QueryParams {
+                    values: param.clone(),
+                    types: param_type.clone(),
+                },
+                variables: variables.clone(),
+            };
+
             let mut target_config = config.clone();
             if let Some(db_name) = database {
                 target_config.database.clone_from(db_name);
             }
-            let client = PostgresClient::new(&target_config).await?;
-            execute_query(&client, sql, &cli.format).await?;
+            let client = connect(&target_config, cli.no_password).await?;
+            execute_query(&client, &scripts, &options, format, &display).await?;
         }
 
-        Commands::Interactive { database } => {
+        Commands::Interactive {
+            database,
+            edit_mode,
+            no_history,
+            hide_secrets,
+        } => {
             let mut target_config = config.clone();
             if let Some(db_name) = database {
                 target_config.database.clone_from(db_name);
             }
-            let client = PostgresClient::new(&target_config).await?;
-            execute_interactive_mode(&client).await?;
+
+            // History is kept per profile, or per server when no profile is used.
+            let history_path = if *no_history {
+                None
+            } else {
+                let connection = profile_name.map_or_else(
+                    || {
+                        format!(
+                            "{}@{}_{}",
+                            target_config.user, target_config.host, target_config.port
+                        )
+                    },
+                    str::to_string,
+                );
+                Some(history_path(&connection, &target_config.database)?)
+            };
+            let options = ReplOptions {
+                edit_mode: *edit_mode,
+                history_path,
+                hide_secrets: *hide_secrets,
+            };
+
+            let client = connect(&target_config, cli.no_password).await?;
+            execute_interactive_mode(client, cli.no_password, display, variables, &options).await?;
         }
+
+        Commands::Profile(_) => unreachable!("profile commands are handled before connecting"),
     }
 
     Ok(())
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+/// Names of the objects in a database, used for tab completion in
+/// interactive mode.
+#[derive(Debug, Clone, Default)]
+pub struct CatalogNames {
+    pub databases: Vec<String>,
+    pub schemas: Vec<String>,
+    /// Tables, views, materialized views, foreign tables and sequences
+    pub relations: Vec<CatalogObject>,
+    pub functions: Vec<CatalogObject>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CatalogObject {
+    pub schema: String,
+    pub name: String,
+    /// Whether the object can be named without its schema, i.e. it is found
+    /// first on the search_path
+    pub visible: bool,
+    /// Column names, for relations
+    pub columns: Vec<String>,
+}
+
+/// A psql-style object name pattern such as `public.*` or `user?`, as
+/// anchored regular expressions for the schema and object name.
+///
+/// `*` matches any sequence of characters and `?` any single character.
+/// Unquoted letters are folded to lower case; double quotes keep case and
+/// make `*`, `?` and `.` literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamePattern {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl std::str::FromStr for NamePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let part = parts.last_mut().expect("at least one part");
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    part.push('"');
+                }
+                '"' => quoted = !quoted,
+                '.' if !quoted => parts.push(String::new()),
+                '*' if !quoted => part.push_str(".*"),
+                '?' if !quoted => part.push('.'),
+                c if quoted => part.push_str(&regex::escape(&c.to_string())),
+                c => part.push_str(&regex::escape(&c.to_lowercase().to_string())),
+            }
+        }
+
+        let anchored = |part: String| format!("^({part})$");
+        match parts.len() {
+            1 => Ok(NamePattern {
+                schema: None,
+                name: anchored(parts.remove(0)),
+            }),
+            2 => {
+                let name = parts.pop().expect("two parts");
+                let schema = parts.pop().expect("two parts");
+                Ok(NamePattern {
+                    schema: Some(anchored(schema)),
+                    // `schema.` matches everything in the schema.
+                    name: anchored(if name.is_empty() {
+                        ".*".to_string()
+                    } else {
+                        name
+                    }),
+                })
+            }
+            _ => Err(format!(
+                "Improper qualified name (too many dotted names): {s}"
+            )),
+        }
+    }
+}
+
+/// A table, view, materialized view, sequence or foreign table, as listed by
+/// `\dt` and its siblings.
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct RelationInfo {
+    #[tabled(rename = "Schema")]
+    pub schema: String,
+
+    #[tabled(rename = "Name")]
+    pub name: String,
+
+    #[tabled(rename = "Type")]
+    pub kind: String,
+
+    #[tabled(rename = "Owner")]
+    pub owner: String,
+
+    #[tabled(rename = "Size")]
+    pub size: String,
+}
+
+/// An index, as listed by `\di`.
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct IndexSummary {
+    #[tabled(rename = "Schema")]
+    pub schema: String,
+
+    #[tabled(rename = "Name")]
+    pub name: String,
+
+    #[tabled(rename = "Table")]
+    pub table: String,
+
+    #[tabled(rename = "Owner")]
+    pub owner: String,
+
+    #[tabled(rename = "Size")]
+    pub size: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    #[tabled(rename = "Schema")]
+    pub schema: String,
+
+    #[tabled(rename = "Name")]
+    pub name: String,
+
+    #[tabled(rename = "Result Type")]
+    pub result_type: String,
+
+    #[tabled(rename = "Arguments")]
+    pub arguments: String,
+
+    #[tabled(rename = "Type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct SchemaInfo {
+    #[tabled(rename = "Name")]
+    pub name: String,
+
+    #[tabled(rename = "Owner")]
+    pub owner: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct RoleInfo {
+    #[tabled(rename = "Role Name")]
+    pub name: String,
+
+    #[tabled(rename = "Attributes")]
+    pub attributes: String,
+
+    #[tabled(rename = "Member Of")]
+    pub member_of: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    /// Whether `pattern` matches `schema.name`, where a pattern without a
+    /// schema matches in any schema.
+    fn matches(pattern: &str, schema: &str, name: &str) -> bool {
+        let pattern: NamePattern = pattern.parse().expect("valid pattern");
+        let is_match = |regex: &str, text: &str| Regex::new(regex).expect("regex").is_match(text);
+        pattern
+            .schema
+            .as_deref()
+            .is_none_or(|regex| is_match(regex, schema))
+            && is_match(&pattern.name, name)
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("user?", "public", "users"));
+        assert!(!matches("user?", "public", "user"));
+        assert!(!matches("user?", "public", "user_id"));
+        assert!(matches("*", "public", "anything"));
+        assert!(matches("ord*", "public", "orders"));
+        assert!(!matches("ord*", "public", "words"));
+        assert!(matches("public.*", "public", "t"));
+        assert!(!matches("public.*", "sales", "t"));
+        // `schema.` is everything in the schema.
+        assert!(matches("sales.", "sales", "t"));
+        assert!(matches("*.t", "sales", "t"));
+    }
+
+    #[test]
+    fn folds_unquoted_letters() {
+        assert!(matches("Orders", "public", "orders"));
+        assert!(!matches("Orders", "public", "Orders"));
+        assert!(matches(r#""Orders""#, "public", "Orders"));
+        assert!(!matches(r#""Orders""#, "public", "orders"));
+        assert!(matches(r#"Sales."Order*""#, "sales", "Order*"));
+    }
+
+    #[test]
+    fn quotes_make_wildcards_and_dots_literal() {
+        assert!(matches(r#""a*b""#, "public", "a*b"));
+        assert!(!matches(r#""a*b""#, "public", "axb"));
+        assert!(matches(r#""My.Schema".t*"#, "My.Schema", "t1"));
+        assert!(!matches(r#""My.Schema".t*"#, "MyXSchema", "t1"));
+        assert!(matches(r#""a""b""#, "public", r#"a"b"#));
+        assert!(matches(r#"pre"Mid"*"#, "public", "preMidpost"));
+    }
+
+    #[test]
+    fn regex_characters_are_literal() {
+        assert!(matches("a+b", "public", "a+b"));
+        assert!(!matches("a+b", "public", "aab"));
+        assert!(matches("t[1]", "public", "t[1]"));
+    }
+
+    #[test]
+    fn too_many_dots() {
+        assert!("a.b.c".parse::<NamePattern>().is_err());
+        assert!(r#""a.b".c"#.parse::<NamePattern>().is_ok());
+    }
+}
@@ -1,6 +1,74 @@
 use serde::{Deserialize, Serialize};
 use tabled::Tabled;
 
+/// TLS negotiation mode, mirroring libpq's `sslmode` parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    /// Whether a TLS connector has to be set up for this mode.
+    pub fn uses_tls(self) -> bool {
+        self != SslMode::Disable
+    }
+
+    /// Whether the server certificate chain must be validated.
+    pub fn verifies_certificate(self) -> bool {
+        matches!(self, SslMode::VerifyCa | SslMode::VerifyFull)
+    }
+
+    /// Whether the server host name must match its certificate.
+    pub fn verifies_hostname(self) -> bool {
+        self == SslMode::VerifyFull
+    }
+
+    /// The transport-level mode understood by tokio-postgres. Certificate
+    /// verification for the `verify-*` modes is enforced by the TLS connector.
+    fn transport_mode(self) -> &'static str {
+        match self {
+            SslMode::Disable => "disable",
+            SslMode::Prefer => "prefer",
+            SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => "require",
+        }
+    }
+}
+
+impl std::fmt::Display for SslMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SslMode::Disable => write!(f, "disable"),
+            SslMode::Prefer => write!(f, "prefer"),
+            SslMode::Require => write!(f, "require"),
+            SslMode::VerifyCa => write!(f, "verify-ca"),
+            SslMode::VerifyFull => write!(f, "verify-full"),
+        }
+    }
+}
+
+impl std::str::FromStr for SslMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disable" => Ok(SslMode::Disable),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(format!(
+                "Invalid sslmode: {s} (expected disable, prefer, require, verify-ca or verify-full)"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ConnectionConfig {
     pub host: String,
@@ -8,23 +76,71 @@ pub struct ConnectionConfig {
     pub user: String,
     pub password: Option<String>,
     pub database: String,
+    pub sslmode: SslMode,
+    /// PEM file with the certificate authorities trusted for the server certificate
+    pub sslrootcert: Option<String>,
+    /// PEM file with the client certificate
+    pub sslcert: Option<String>,
+    /// PEM file with the PKCS#8 private key for `sslcert`
+    pub sslkey: Option<String>,
+    /// Schema searched before the rest of the search path
+    pub schema: Option<String>,
+    /// Replacement for the server's default `search_path`
+    pub search_path: Option<String>,
+    pub timeouts: SessionTimeouts,
+}
+
+/// Time limits set for the session once connected. Values are milliseconds,
+/// or carry a unit such as `30s` or `5min`; the server checks them.
+#[derive(Debug, Clone, Default)]
+pub struct SessionTimeouts {
+    pub statement: Option<String>,
+    pub lock: Option<String>,
+    pub idle_in_transaction: Option<String>,
+}
+
+impl SessionTimeouts {
+    /// The limits that are set, by server parameter name.
+    pub fn settings(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("statement_timeout", &self.statement),
+            ("lock_timeout", &self.lock),
+            (
+                "idle_in_transaction_session_timeout",
+                &self.idle_in_transaction,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+        .collect()
+    }
 }
 
 impl ConnectionConfig {
     pub fn to_connection_string(&self) -> String {
         let mut conn_str = format!(
-            "host={} port={} user={} dbname={}",
-            self.host, self.port, self.user, self.database
+            "host={} port={} user={} dbname={} sslmode={}",
+            quote_value(&self.host),
+            self.port,
+            quote_value(&self.user),
+            quote_value(&self.database),
+            self.sslmode.transport_mode()
         );
 
         if let Some(password) = &self.password {
-            conn_str.push_str(&format!(" password={password}"));
+            conn_str.push_str(&format!(" password={}", quote_value(password)));
         }
 
         conn_str
     }
 }
 
+/// Quotes a value for a `key=value` connection string so that passwords and
+/// names containing spaces, quotes or backslashes survive parsing.
+fn quote_value(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
 #[derive(Debug, Serialize, Deserialize, Tabled)]
 pub struct ConnectionInfo {
     #[tabled(rename = "Property")]
@@ -18,3 +18,125 @@ pub struct DatabaseInfo {
     #[tabled(rename = "Description")]
     pub description: String,
 }
+
+/// Size, activity and configuration of a single database, reported by
+/// `database info`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DatabaseDetails {
+    pub name: String,
+    pub owner: String,
+    pub encoding: String,
+    pub collation: String,
+    pub ctype: String,
+    pub tablespace: String,
+    /// `-1` means no limit
+    pub connection_limit: i32,
+    pub size: String,
+    pub size_bytes: i64,
+    /// Age of `datfrozenxid` in transactions; wraparound happens at about 2^31
+    pub frozen_xid_age: i32,
+    pub connections: i32,
+    pub xact_commit: i64,
+    pub xact_rollback: i64,
+    /// Percentage of block reads served from shared buffers, if any reads happened
+    pub cache_hit_ratio: Option<f64>,
+    pub deadlocks: i64,
+    pub temp_files: i64,
+    pub temp_bytes: String,
+    pub stats_reset: Option<String>,
+    pub largest_relations: Vec<RelationSize>,
+    pub extensions: Vec<ExtensionInfo>,
+}
+
+impl DatabaseDetails {
+    /// The scalar details as property/value pairs, for table output.
+    pub fn properties(&self) -> Vec<DatabaseProperty> {
+        let mut properties = Vec::new();
+        let mut push = |property: &str, value: String| {
+            properties.push(DatabaseProperty {
+                property: property.to_string(),
+                value,
+            });
+        };
+
+        push("Database", self.name.clone());
+        push("Owner", self.owner.clone());
+        push("Encoding", self.encoding.clone());
+        push("Collation", self.collation.clone());
+        push("Ctype", self.ctype.clone());
+        push("Tablespace", self.tablespace.clone());
+        push(
+            "Connection Limit",
+            match self.connection_limit {
+                -1 => "unlimited".to_string(),
+                limit => limit.to_string(),
+            },
+        );
+        push("Size", self.size.clone());
+        push("Connections", self.connections.to_string());
+        push("Commits", self.xact_commit.to_string());
+        push("Rollbacks", self.xact_rollback.to_string());
+        push(
+            "Cache Hit Ratio",
+            self.cache_hit_ratio
+                .map_or_else(|| "n/a".to_string(), |ratio| format!("{ratio:.2}%")),
+        );
+        push("Deadlocks", self.deadlocks.to_string());
+        push(
+            "Temp Files",
+            format!("{} ({})", self.temp_files, self.temp_bytes),
+        );
+        push(
+            "Frozen XID Age",
+            format!(
+                "{} ({:.1}% of wraparound limit)",
+                self.frozen_xid_age,
+                f64::from(self.frozen_xid_age) / 2_147_483_648.0 * 100.0
+            ),
+        );
+        if let Some(reset) = &self.stats_reset {
+            push("Stats Reset", reset.clone());
+        }
+
+        properties
+    }
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct DatabaseProperty {
+    #[tabled(rename = "Property")]
+    pub property: String,
+
+    #[tabled(rename = "Value")]
+    pub value: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct RelationSize {
+    #[tabled(rename = "Relation")]
+    pub name: String,
+
+    #[tabled(rename = "Type")]
+    pub kind: String,
+
+    #[tabled(rename = "Total Size")]
+    pub size: String,
+
+    #[tabled(skip)]
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    #[tabled(rename = "Extension")]
+    pub name: String,
+
+    #[tabled(rename = "Version")]
+    pub version: String,
+
+    #[tabled(rename = "Schema")]
+    pub schema: String,
+
+    #[tabled(rename = "Description")]
+    pub description: String,
+}
@@ -1,3 +1,6 @@
+pub mod catalog;
 pub mod connection;
 pub mod database;
+pub mod profile;
 pub mod table;
+pub mod timing;
@@ -0,0 +1,118 @@
+use crate::cli::OutputFormat;
+use crate::models::connection::SslMode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tabled::Tabled;
+
+/// A set of connection and display settings. Every field is optional so the
+/// same type describes a saved profile as well as the environment and
+/// command-line layers it gets merged with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Only ever supplied by flags, the environment or a service file; it is
+    /// never read from or written to `config.toml`.
+    #[serde(skip)]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sslmode: Option<SslMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sslrootcert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sslcert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sslkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+}
+
+impl Profile {
+    /// Fills every unset field from `lower`, keeping the values of `self`.
+    pub fn or(self, lower: Profile) -> Profile {
+        Profile {
+            host: self.host.or(lower.host),
+            port: self.port.or(lower.port),
+            user: self.user.or(lower.user),
+            database: self.database.or(lower.database),
+            password: self.password.or(lower.password),
+            sslmode: self.sslmode.or(lower.sslmode),
+            sslrootcert: self.sslrootcert.or(lower.sslrootcert),
+            sslcert: self.sslcert.or(lower.sslcert),
+            sslkey: self.sslkey.or(lower.sslkey),
+            schema: self.schema.or(lower.schema),
+            search_path: self.search_path.or(lower.search_path),
+            format: self.format.or(lower.format),
+            color: self.color.or(lower.color),
+        }
+    }
+
+    /// Lists the settings that are present as property/value pairs.
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let mut settings = Vec::new();
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                settings.push((name, value));
+            }
+        };
+
+        push("host", self.host.clone());
+        push("port", self.port.map(|p| p.to_string()));
+        push("user", self.user.clone());
+        push("database", self.database.clone());
+        push("sslmode", self.sslmode.map(|m| m.to_string()));
+        push("sslrootcert", self.sslrootcert.clone());
+        push("sslcert", self.sslcert.clone());
+        push("sslkey", self.sslkey.clone());
+        push("schema", self.schema.clone());
+        push("search_path", self.search_path.clone());
+        push("format", self.format.as_ref().map(ToString::to_string));
+        push("color", self.color.map(|c| c.to_string()));
+
+        settings
+    }
+}
+
+/// Contents of `config.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DconConfig {
+    /// Profile used when `--profile` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    #[tabled(rename = "Profile")]
+    pub name: String,
+
+    #[tabled(rename = "Host")]
+    pub host: String,
+
+    #[tabled(rename = "Port")]
+    pub port: String,
+
+    #[tabled(rename = "User")]
+    pub user: String,
+
+    #[tabled(rename = "Database")]
+    pub database: String,
+
+    #[tabled(rename = "Default")]
+    pub is_default: String,
+}
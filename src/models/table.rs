@@ -32,4 +32,384 @@ pub struct ColumnInfo {
 
     #[tabled(rename = "Primary Key")]
     pub is_primary: String,
+
+    #[tabled(rename = "Storage")]
+    pub storage: String,
+
+    #[tabled(rename = "Description", display_with = "display_option")]
+    pub comment: Option<String>,
+}
+
+/// Everything `table describe` reports about a relation, in the spirit of
+/// psql's `\d+`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableDetails {
+    pub schema: String,
+    pub name: String,
+    pub kind: String,
+    pub owner: String,
+    pub tablespace: String,
+    pub comment: Option<String>,
+    /// Planner estimate from the last VACUUM/ANALYZE, if there was one
+    pub row_estimate: Option<i64>,
+    pub heap_size: String,
+    pub toast_size: Option<String>,
+    pub indexes_size: String,
+    pub total_size: String,
+    pub row_level_security: bool,
+    pub force_row_level_security: bool,
+    /// Partition key of a partitioned table, e.g. `RANGE (created_at)`
+    pub partition_key: Option<String>,
+    /// Parent of a partition, with its bound
+    pub partition_of: Option<PartitionInfo>,
+    pub columns: Vec<ColumnInfo>,
+    pub indexes: Vec<IndexInfo>,
+    pub constraints: Vec<ConstraintInfo>,
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+    pub referenced_by: Vec<ForeignKeyInfo>,
+    pub triggers: Vec<TriggerInfo>,
+    pub policies: Vec<PolicyInfo>,
+    pub partitions: Vec<PartitionInfo>,
+}
+
+impl TableDetails {
+    /// The scalar details as property/value pairs, for table output.
+    pub fn properties(&self) -> Vec<TableProperty> {
+        let mut properties = Vec::new();
+        let mut push = |property: &str, value: String| {
+            properties.push(TableProperty {
+                property: property.to_string(),
+                value,
+            });
+        };
+
+        push("Table", format!("{}.{}", self.schema, self.name));
+        push("Type", self.kind.clone());
+        push("Owner", self.owner.clone());
+        push("Tablespace", self.tablespace.clone());
+        push(
+            "Estimated Rows",
+            self.row_estimate
+                .map_or_else(|| "unknown".to_string(), |rows| rows.to_string()),
+        );
+        push("Heap Size", self.heap_size.clone());
+        push(
+            "TOAST Size",
+            self.toast_size
+                .clone()
+                .unwrap_or_else(|| "none".to_string()),
+        );
+        push("Indexes Size", self.indexes_size.clone());
+        push("Total Size", self.total_size.clone());
+        push(
+            "Row Level Security",
+            match (self.row_level_security, self.force_row_level_security) {
+                (true, true) => "enabled (forced)",
+                (true, false) => "enabled",
+                (false, _) => "disabled",
+            }
+            .to_string(),
+        );
+        if let Some(key) = &self.partition_key {
+            push("Partition Key", key.clone());
+        }
+        if let Some(parent) = &self.partition_of {
+            push("Partition Of", format!("{} {}", parent.name, parent.bound));
+        }
+        if let Some(comment) = &self.comment {
+            push("Description", comment.clone());
+        }
+
+        properties
+    }
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct TableProperty {
+    #[tabled(rename = "Property")]
+    pub property: String,
+
+    #[tabled(rename = "Value")]
+    pub value: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct IndexInfo {
+    #[tabled(rename = "Index Name")]
+    pub name: String,
+
+    #[tabled(rename = "Definition")]
+    pub definition: String,
+
+    #[tabled(rename = "Primary")]
+    pub is_primary: bool,
+
+    #[tabled(rename = "Unique")]
+    pub is_unique: bool,
+
+    #[tabled(rename = "Valid")]
+    pub is_valid: bool,
+
+    #[tabled(rename = "Size")]
+    pub size: String,
+}
+
+/// A primary key, unique, check or exclusion constraint.
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct ConstraintInfo {
+    #[tabled(rename = "Constraint Name")]
+    pub name: String,
+
+    #[tabled(rename = "Type")]
+    pub constraint_type: String,
+
+    #[tabled(rename = "Definition")]
+    pub definition: String,
+}
+
+/// A foreign key. `table` is the other side of the relationship: the
+/// referenced table for keys defined on the described table, and the
+/// referencing table for keys pointing at it.
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct ForeignKeyInfo {
+    #[tabled(rename = "Constraint Name")]
+    pub name: String,
+
+    #[tabled(rename = "Table")]
+    pub table: String,
+
+    #[tabled(rename = "Definition")]
+    pub definition: String,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    #[tabled(rename = "Trigger Name")]
+    pub name: String,
+
+    #[tabled(rename = "Enabled")]
+    pub enabled: String,
+
+    #[tabled(rename = "Definition")]
+    pub definition: String,
+}
+
+/// A row-level security policy.
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct PolicyInfo {
+    #[tabled(rename = "Policy Name")]
+    pub name: String,
+
+    #[tabled(rename = "Command")]
+    pub command: String,
+
+    #[tabled(rename = "Permissive")]
+    pub permissive: bool,
+
+    #[tabled(rename = "Roles")]
+    pub roles: String,
+
+    #[tabled(rename = "Using", display_with = "display_option")]
+    pub using: Option<String>,
+
+    #[tabled(rename = "With Check", display_with = "display_option")]
+    pub with_check: Option<String>,
+}
+
+#[derive(Debug, Tabled, Serialize, Deserialize)]
+pub struct PartitionInfo {
+    #[tabled(rename = "Partition")]
+    pub name: String,
+
+    #[tabled(rename = "Bound")]
+    pub bound: String,
+}
+
+fn display_option(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+/// A possibly schema-qualified table name such as `sales.orders` or
+/// `"Sales"."Order Items"`.
+///
+/// Parsing follows SQL rules: unquoted parts are folded to lower case and
+/// quoted parts are kept verbatim, with `""` standing for a literal quote.
+/// Names without a schema are resolved by the server through `search_path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualifiedName {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl QualifiedName {
+    pub fn new(schema: &str, name: &str) -> Self {
+        QualifiedName {
+            schema: Some(schema.to_string()),
+            name: name.to_string(),
+        }
+    }
+
+    /// The name as a SQL identifier with every part quoted.
+    pub fn quoted(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", quote_ident(schema), quote_ident(&self.name)),
+            None => quote_ident(&self.name),
+        }
+    }
+}
+
+/// Shows the name the way psql does, quoting only the parts that need it.
+impl std::fmt::Display for QualifiedName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.schema {
+            Some(schema) => write!(f, "{}.{}", display_ident(schema), display_ident(&self.name)),
+            None => write!(f, "{}", display_ident(&self.name)),
+        }
+    }
+}
+
+impl std::str::FromStr for QualifiedName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut chars = s.trim().chars().peekable();
+
+        loop {
+            let part = if chars.next_if_eq(&'"').is_some() {
+                let mut part = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.next_if_eq(&'"').is_some() => part.push('"'),
+                        Some('"') => break,
+                        Some(c) => part.push(c),
+                        None => return Err(format!("Unterminated quoted identifier in '{s}'")),
+                    }
+                }
+                part
+            } else {
+                let mut part = String::new();
+                while let Some(c) = chars.next_if(|&c| c != '.') {
+                    if c == '"' || c.is_whitespace() {
+                        return Err(format!("Invalid table name '{s}'"));
+                    }
+                    part.push(c.to_ascii_lowercase());
+                }
+                part
+            };
+
+            if part.is_empty() {
+                return Err(format!("Invalid table name '{s}'"));
+            }
+            parts.push(part);
+
+            match chars.next() {
+                None => break,
+                Some('.') => {}
+                Some(c) => return Err(format!("Unexpected '{c}' in table name '{s}'")),
+            }
+        }
+
+        match <[String; 2]>::try_from(parts) {
+            Ok([schema, name]) => Ok(QualifiedName {
+                schema: Some(schema),
+                name,
+            }),
+            Err(mut parts) if parts.len() == 1 => Ok(QualifiedName {
+                schema: None,
+                name: parts.remove(0),
+            }),
+            Err(_) => Err(format!(
+                "Too many dotted names in '{s}' (expected [schema.]table)"
+            )),
+        }
+    }
+}
+
+/// Quotes an identifier for use in SQL.
+pub fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Writes an identifier the way psql shows it, quoting it only when it would
+/// otherwise be folded to lower case or is not a plain word.
+pub fn display_ident(ident: &str) -> String {
+    let plain = ident.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && ident
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$');
+    if plain {
+        ident.to_string()
+    } else {
+        quote_ident(ident)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> (Option<String>, String) {
+        let name: QualifiedName = s.parse().expect("valid name");
+        (name.schema, name.name)
+    }
+
+    #[test]
+    fn parses_names() {
+        let cases = [
+            ("orders", None, "orders"),
+            ("  Orders ", None, "orders"),
+            ("Sales.Orders", Some("sales"), "orders"),
+            (r#""Sales"."Order Items""#, Some("Sales"), "Order Items"),
+            (r#""My.Schema"."t""#, Some("My.Schema"), "t"),
+            (r#"public."a""b""#, Some("public"), r#"a"b"#),
+            (r#""""".x"#, Some(r#"""#), "x"),
+            ("t$1", None, "t$1"),
+        ];
+        for (input, schema, name) in cases {
+            assert_eq!(
+                parse(input),
+                (schema.map(str::to_string), name.to_string()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_names() {
+        for input in [
+            "",
+            "a.b.c",
+            "a..b",
+            ".t",
+            "s.",
+            "my table",
+            r#"a"b"#,
+            r#""unterminated"#,
+            r#""a"x"#,
+            r#""""#,
+        ] {
+            assert!(input.parse::<QualifiedName>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn quotes_names_for_sql_and_display() {
+        let name = QualifiedName::new("My.Schema", r#"a"b"#);
+        assert_eq!(name.quoted(), r#""My.Schema"."a""b""#);
+        assert_eq!(name.to_string(), r#""My.Schema"."a""b""#);
+        assert_eq!(name.to_string().parse(), Ok(name));
+
+        let name = QualifiedName::new("sales", "order_items");
+        assert_eq!(name.quoted(), r#""sales"."order_items""#);
+        assert_eq!(name.to_string(), "sales.order_items");
+    }
+
+    #[test]
+    fn display_quotes_only_when_needed() {
+        assert_eq!(display_ident("_order$1"), "_order$1");
+        assert_eq!(display_ident("Order"), r#""Order""#);
+        assert_eq!(display_ident("1st"), r#""1st""#);
+        assert_eq!(display_ident("a b"), r#""a b""#);
+    }
 }
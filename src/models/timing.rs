@@ -0,0 +1,87 @@
+use serde_json::Value;
+use std::time::Duration;
+
+/// What the client saw of a statement, reported by `--timing`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryTiming {
+    /// From preparing the statement until its last row was read
+    pub elapsed: Duration,
+    /// Until the first row arrived, if any did
+    pub first_row: Option<Duration>,
+    pub rows: u64,
+    /// Size of the values received, without the protocol's framing
+    pub bytes: u64,
+}
+
+/// Figures the server reports for a statement under
+/// `EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON)`, shown by `--stats`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerStats {
+    pub planning_ms: f64,
+    pub execution_ms: f64,
+    /// Buffers used by the plan, counted in blocks
+    pub shared_hit: u64,
+    pub shared_read: u64,
+    pub shared_dirtied: u64,
+    pub shared_written: u64,
+    pub temp_read: u64,
+    pub temp_written: u64,
+}
+
+impl ServerStats {
+    /// Reads the figures from the JSON output of `EXPLAIN`, a one-element
+    /// array. Anything missing counts as 0.
+    pub fn from_explain(explain: &Value) -> Self {
+        let top = &explain[0];
+        let plan = &top["Plan"];
+        let blocks = |name: &str| plan[name].as_u64().unwrap_or_default();
+
+        ServerStats {
+            planning_ms: top["Planning Time"].as_f64().unwrap_or_default(),
+            execution_ms: top["Execution Time"].as_f64().unwrap_or_default(),
+            shared_hit: blocks("Shared Hit Blocks"),
+            shared_read: blocks("Shared Read Blocks"),
+            shared_dirtied: blocks("Shared Dirtied Blocks"),
+            shared_written: blocks("Shared Written Blocks"),
+            temp_read: blocks("Temp Read Blocks"),
+            temp_written: blocks("Temp Written Blocks"),
+        }
+    }
+
+    /// Buffer usage the way `EXPLAIN` prints it, such as
+    /// `shared hit=12 read=3, temp written=8`, leaving out zero counts.
+    pub fn buffers(&self) -> String {
+        let group = |name: &str, counts: &[(&str, u64)]| {
+            let counts: Vec<String> = counts
+                .iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(label, count)| format!("{label}={count}"))
+                .collect();
+            (!counts.is_empty()).then(|| format!("{name} {}", counts.join(" ")))
+        };
+        let groups: Vec<String> = [
+            group(
+                "shared",
+                &[
+                    ("hit", self.shared_hit),
+                    ("read", self.shared_read),
+                    ("dirtied", self.shared_dirtied),
+                    ("written", self.shared_written),
+                ],
+            ),
+            group(
+                "temp",
+                &[("read", self.temp_read), ("written", self.temp_written)],
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if groups.is_empty() {
+            "none".to_string()
+        } else {
+            groups.join(", ")
+        }
+    }
+}